@@ -0,0 +1,84 @@
+// Instructions per second of headless runs: interpreted without and with the decoded instruction
+// cache, and run from translated blocks.
+//
+// cargo bench --no-default-features
+
+use std::{hint::black_box, time::Instant};
+
+use chip_8_rs::{assemble, Backend, Chip8};
+
+const FRAMES: usize = 100;
+const RUNS: usize = 40;
+const CLOCK_RATE: u32 = 1_000_000;
+
+// Arithmetic, memory and a subroutine in a loop, without any draw to end frames early
+const LOOP: &str = "
+    ld i, data
+loop:
+    add v0, 1
+    ld v1, v0
+    shr v1
+    xor v2, v1
+    call store
+    sne v0, 0
+    add v3, 1
+    jp loop
+store:
+    ld [i], v3
+    ret
+data:
+    db 0, 0, 0, 0
+";
+
+// Best of a few runs, the others are slowed down by whatever else the machine is doing
+fn instructions_per_second(rom: &[u8], backend: Backend, cache: bool) -> f64 {
+    (0..RUNS)
+        .map(|_| run(rom, backend, cache))
+        .fold(0.0, f64::max)
+}
+
+fn run(rom: &[u8], backend: Backend, cache: bool) -> f64 {
+    let mut chip8 = Chip8::new();
+    chip8.set_clock_rate(CLOCK_RATE);
+    chip8.set_seed(0);
+    chip8.set_backend(backend);
+    chip8.set_instruction_cache(cache);
+    chip8.load_rom(rom).unwrap();
+
+    let start = Instant::now();
+
+    for _ in 0..FRAMES {
+        black_box(chip8.run_frame()).unwrap();
+    }
+
+    chip8.instruction_count() as f64 / start.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let programs = [
+        ("loop", assemble(LOOP).unwrap()),
+        (
+            "test_opcode",
+            std::fs::read("programs/test_opcode.ch8").unwrap(),
+        ),
+        (
+            "flightrunner",
+            std::fs::read("programs/flightrunner.ch8").unwrap(),
+        ),
+    ];
+
+    for (name, rom) in &programs {
+        let uncached = instructions_per_second(rom, Backend::Interpreter, false);
+        let cached = instructions_per_second(rom, Backend::Interpreter, true);
+        let blocks = instructions_per_second(rom, Backend::Blocks, true);
+
+        println!(
+            "{name:>12}: {:>7.1} M/s uncached, {:>7.1} M/s cached ({:.2}x), {:>7.1} M/s blocks ({:.2}x)",
+            uncached / 1e6,
+            cached / 1e6,
+            cached / uncached,
+            blocks / 1e6,
+            blocks / uncached
+        );
+    }
+}
@@ -0,0 +1,537 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::cpu::PROGRAM_STARTING_ADDRESS;
+
+const MAX_INCLUDE_DEPTH: usize = 16;
+const MAX_CONSTANT_DEPTH: usize = 16;
+
+// A statement with its label, e.g. `loop: ADD V0, 1`, once comments and includes are gone
+struct Statement {
+    location: String,
+    label: Option<String>,
+    kind: StatementKind,
+}
+
+enum StatementKind {
+    Empty,
+    Constant {
+        name: String,
+        value: String,
+    },
+    Instruction {
+        mnemonic: String,
+        operands: Vec<String>,
+    },
+}
+
+struct Assembler {
+    constants: HashMap<String, String>,
+    labels: HashMap<String, u16>,
+}
+
+/// Assembles source code into a ROM loaded at 0x200, includes are relative to the working
+/// directory.
+pub fn assemble(source: &str) -> Result<Vec<u8>, String> {
+    let mut statements = vec![];
+
+    parse_source(source, "<source>", Path::new("."), 0, &mut statements)?;
+
+    Assembler::new().run(&statements)
+}
+
+/// Assembles a source file, includes are relative to the file including them.
+pub fn assemble_file(path: impl AsRef<Path>) -> Result<Vec<u8>, String> {
+    let path = path.as_ref();
+
+    let source = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+
+    let mut statements = vec![];
+
+    parse_source(
+        &source,
+        &path.display().to_string(),
+        &parent_directory(path),
+        0,
+        &mut statements,
+    )?;
+
+    Assembler::new().run(&statements)
+}
+
+fn parent_directory(path: &Path) -> PathBuf {
+    path.parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
+
+fn parse_source(
+    source: &str,
+    name: &str,
+    directory: &Path,
+    depth: usize,
+    statements: &mut Vec<Statement>,
+) -> Result<(), String> {
+    for (index, line) in source.lines().enumerate() {
+        let location = format!("{name}:{}", index + 1);
+
+        // Everything after a ';' is a comment
+        let text = line.split(';').next().unwrap_or_default().trim();
+
+        if let Some(path) = include_path(text) {
+            let path = path.map_err(|e| format!("{location}: {e}"))?;
+
+            if depth == MAX_INCLUDE_DEPTH {
+                return Err(format!("{location}: includes are nested too deeply"));
+            }
+
+            let path = directory.join(path);
+            let source = fs::read_to_string(&path)
+                .map_err(|e| format!("{location}: cannot include {}: {e}", path.display()))?;
+
+            parse_source(
+                &source,
+                &path.display().to_string(),
+                &parent_directory(&path),
+                depth + 1,
+                statements,
+            )?;
+        } else {
+            let statement =
+                parse_statement(text, location.clone()).map_err(|e| format!("{location}: {e}"))?;
+
+            statements.push(statement);
+        }
+    }
+
+    Ok(())
+}
+
+fn include_path(text: &str) -> Option<Result<&str, String>> {
+    let (keyword, path) = split_word(text);
+
+    if !keyword.eq_ignore_ascii_case("include") {
+        return None;
+    }
+
+    let path = path
+        .strip_prefix('"')
+        .and_then(|path| path.strip_suffix('"'))
+        .ok_or_else(|| String::from("expected a quoted path after include"));
+
+    Some(path)
+}
+
+fn parse_statement(text: &str, location: String) -> Result<Statement, String> {
+    let mut text = text;
+    let mut label = None;
+
+    let (first, rest) = split_word(text);
+
+    if let Some(name) = first.strip_suffix(':') {
+        if !is_identifier(name) {
+            return Err(format!("invalid label name '{name}'"));
+        }
+
+        label = Some(String::from(name));
+        text = rest;
+    }
+
+    let (first, rest) = split_word(text);
+
+    let kind = if first.is_empty() {
+        StatementKind::Empty
+    } else {
+        let (second, value) = split_word(rest);
+
+        if second.eq_ignore_ascii_case("equ") {
+            if !is_identifier(first) {
+                return Err(format!("invalid constant name '{first}'"));
+            }
+
+            StatementKind::Constant {
+                name: String::from(first),
+                value: String::from(value),
+            }
+        } else {
+            let operands = if rest.is_empty() {
+                vec![]
+            } else {
+                rest.split(',')
+                    .map(|operand| String::from(operand.trim()))
+                    .collect()
+            };
+
+            StatementKind::Instruction {
+                mnemonic: first.to_ascii_uppercase(),
+                operands,
+            }
+        }
+    };
+
+    Ok(Statement {
+        location,
+        label,
+        kind,
+    })
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    match text.split_once(char::is_whitespace) {
+        Some((word, rest)) => (word, rest.trim()),
+        None => (text, ""),
+    }
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && register(name).is_none()
+}
+
+fn register(operand: &str) -> Option<u16> {
+    let digit = operand
+        .strip_prefix('V')
+        .or_else(|| operand.strip_prefix('v'))?;
+
+    if digit.len() != 1 {
+        return None;
+    }
+
+    u16::from_str_radix(digit, 16).ok()
+}
+
+fn parse_number(text: &str) -> Option<i64> {
+    let (digits, radix) = if let Some(digits) = text.strip_prefix(['#', '$']) {
+        (digits, 16)
+    } else if let Some(digits) = text.strip_prefix("0x") {
+        (digits, 16)
+    } else if let Some(digits) = text.strip_prefix("0b") {
+        (digits, 2)
+    } else {
+        (text, 10)
+    };
+
+    i64::from_str_radix(digits, radix).ok()
+}
+
+impl Assembler {
+    fn new() -> Assembler {
+        Assembler {
+            constants: HashMap::new(),
+            labels: HashMap::new(),
+        }
+    }
+
+    fn run(&mut self, statements: &[Statement]) -> Result<Vec<u8>, String> {
+        self.collect_symbols(statements)?;
+
+        let mut rom = vec![];
+
+        for statement in statements {
+            if let StatementKind::Instruction { mnemonic, operands } = &statement.kind {
+                let bytes = self
+                    .encode(mnemonic, operands)
+                    .map_err(|e| format!("{}: {e}", statement.location))?;
+
+                rom.extend(bytes);
+            }
+        }
+
+        Ok(rom)
+    }
+
+    // First pass, labels only need the size of every instruction to get their address
+    fn collect_symbols(&mut self, statements: &[Statement]) -> Result<(), String> {
+        let mut address = PROGRAM_STARTING_ADDRESS;
+
+        for statement in statements {
+            let location = &statement.location;
+
+            if let Some(label) = &statement.label {
+                if self.is_defined(label) {
+                    return Err(format!("{location}: '{label}' is already defined"));
+                }
+
+                let address = u16::try_from(address)
+                    .map_err(|_| format!("{location}: '{label}' is past the end of memory"))?;
+
+                self.labels.insert(label.clone(), address);
+            }
+
+            match &statement.kind {
+                StatementKind::Empty => (),
+                StatementKind::Constant { name, value } => {
+                    if self.is_defined(name) {
+                        return Err(format!("{location}: '{name}' is already defined"));
+                    }
+
+                    self.constants.insert(name.clone(), value.clone());
+                }
+                StatementKind::Instruction { mnemonic, operands } => {
+                    address += Assembler::size(mnemonic, operands);
+
+                    if address > 0x10000 {
+                        return Err(format!("{location}: the program does not fit in memory"));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_defined(&self, name: &str) -> bool {
+        self.labels.contains_key(name) || self.constants.contains_key(name)
+    }
+
+    fn size(mnemonic: &str, operands: &[String]) -> usize {
+        match mnemonic {
+            "DB" => operands.len(),
+            "DW" => operands.len() * 2,
+            "LD" if operands.len() == 2 && Assembler::long_operand(&operands[1]).is_some() => 4,
+            _ => 2,
+        }
+    }
+
+    fn long_operand(operand: &str) -> Option<&str> {
+        let (keyword, value) = split_word(operand);
+
+        if keyword.eq_ignore_ascii_case("long") {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    fn encode(&self, mnemonic: &str, operands: &[String]) -> Result<Vec<u8>, String> {
+        let operands: Vec<&str> = operands.iter().map(String::as_str).collect();
+
+        let opcode = match (mnemonic, operands.as_slice()) {
+            ("DB", values) => {
+                return values.iter().map(|value| self.byte(value)).collect();
+            }
+            ("DW", values) => {
+                let mut bytes = vec![];
+
+                for value in values {
+                    bytes.extend(self.value(value, 0xFFFF)?.to_be_bytes());
+                }
+
+                return Ok(bytes);
+            }
+            ("CLS", []) => 0x00E0,
+            ("RET", []) => 0x00EE,
+            ("SCD", [n]) => 0x00C0 | self.nibble(n)?,
+            ("SCU", [n]) => 0x00D0 | self.nibble(n)?,
+            ("SCR", []) => 0x00FB,
+            ("SCL", []) => 0x00FC,
+            ("EXIT", []) => 0x00FD,
+            ("LOW", []) => 0x00FE,
+            ("HIGH", []) => 0x00FF,
+            ("SYS", [address]) => self.address(address)?,
+            ("JP", [v0, address]) if register(v0) == Some(0) => 0xB000 | self.address(address)?,
+            ("JP", [address]) => 0x1000 | self.address(address)?,
+            ("CALL", [address]) => 0x2000 | self.address(address)?,
+            ("SE", [x, y]) => match register(y) {
+                Some(y) => 0x5000 | Assembler::vx(x)? | y << 4,
+                None => 0x3000 | Assembler::vx(x)? | self.byte(y)? as u16,
+            },
+            ("SNE", [x, y]) => match register(y) {
+                Some(y) => 0x9000 | Assembler::vx(x)? | y << 4,
+                None => 0x4000 | Assembler::vx(x)? | self.byte(y)? as u16,
+            },
+            ("SAVE", [x, y]) => 0x5002 | Assembler::vx(x)? | Assembler::vy(y)?,
+            ("LOAD", [x, y]) => 0x5003 | Assembler::vx(x)? | Assembler::vy(y)?,
+            ("LD", [destination, source]) => return self.encode_load(destination, source),
+            ("ADD", [i, x]) if i.eq_ignore_ascii_case("I") => 0xF01E | Assembler::vx(x)?,
+            ("ADD", [x, y]) => match register(y) {
+                Some(y) => 0x8004 | Assembler::vx(x)? | y << 4,
+                None => 0x7000 | Assembler::vx(x)? | self.byte(y)? as u16,
+            },
+            ("OR", [x, y]) => 0x8001 | Assembler::vx(x)? | Assembler::vy(y)?,
+            ("AND", [x, y]) => 0x8002 | Assembler::vx(x)? | Assembler::vy(y)?,
+            ("XOR", [x, y]) => 0x8003 | Assembler::vx(x)? | Assembler::vy(y)?,
+            ("SUB", [x, y]) => 0x8005 | Assembler::vx(x)? | Assembler::vy(y)?,
+            ("SHR", [x]) => 0x8006 | Assembler::vx(x)?,
+            ("SHR", [x, y]) => 0x8006 | Assembler::vx(x)? | Assembler::vy(y)?,
+            ("SUBN", [x, y]) => 0x8007 | Assembler::vx(x)? | Assembler::vy(y)?,
+            ("SHL", [x]) => 0x800E | Assembler::vx(x)?,
+            ("SHL", [x, y]) => 0x800E | Assembler::vx(x)? | Assembler::vy(y)?,
+            ("RND", [x, kk]) => 0xC000 | Assembler::vx(x)? | self.byte(kk)? as u16,
+            ("DRW", [x, y, n]) => {
+                0xD000 | Assembler::vx(x)? | Assembler::vy(y)? | self.nibble(n)?
+            }
+            ("SKP", [x]) => 0xE09E | Assembler::vx(x)?,
+            ("SKNP", [x]) => 0xE0A1 | Assembler::vx(x)?,
+            ("PLANE", [n]) => 0xF001 | self.nibble(n)? << 8,
+            ("AUDIO", []) => 0xF002,
+            ("PITCH", [x]) => 0xF03A | Assembler::vx(x)?,
+            (
+                "CLS" | "RET" | "SCD" | "SCU" | "SCR" | "SCL" | "EXIT" | "LOW" | "HIGH" | "SYS"
+                | "JP" | "CALL" | "SE" | "SNE" | "SAVE" | "LOAD" | "LD" | "ADD" | "OR" | "AND"
+                | "XOR" | "SUB" | "SHR" | "SUBN" | "SHL" | "RND" | "DRW" | "SKP" | "SKNP" | "PLANE"
+                | "AUDIO" | "PITCH",
+                _,
+            ) => return Err(format!("invalid operands for {mnemonic}")),
+            _ => return Err(format!("unknown instruction '{mnemonic}'")),
+        };
+
+        Ok(u16::to_be_bytes(opcode).to_vec())
+    }
+
+    fn encode_load(&self, destination: &str, source: &str) -> Result<Vec<u8>, String> {
+        let destination_upper = destination.to_ascii_uppercase();
+        let source_upper = source.to_ascii_uppercase();
+
+        let opcode = if let Some(x) = register(destination) {
+            let x = x << 8;
+
+            match source_upper.as_str() {
+                "DT" => 0xF007 | x,
+                "K" => 0xF00A | x,
+                "[I]" => 0xF065 | x,
+                "R" => 0xF085 | x,
+                _ => match register(source) {
+                    Some(y) => 0x8000 | x | y << 4,
+                    None => 0x6000 | x | self.byte(source)? as u16,
+                },
+            }
+        } else {
+            match destination_upper.as_str() {
+                "I" => match Assembler::long_operand(source) {
+                    Some(address) => {
+                        let address = self.value(address, 0xFFFF)?;
+
+                        return Ok([0xF0, 0x00, (address >> 8) as u8, address as u8].to_vec());
+                    }
+                    None => 0xA000 | self.address(source)?,
+                },
+                "DT" => 0xF015 | Assembler::vx(source)?,
+                "ST" => 0xF018 | Assembler::vx(source)?,
+                "F" => 0xF029 | Assembler::vx(source)?,
+                "HF" => 0xF030 | Assembler::vx(source)?,
+                "B" => 0xF033 | Assembler::vx(source)?,
+                "[I]" => 0xF055 | Assembler::vx(source)?,
+                "R" => 0xF075 | Assembler::vx(source)?,
+                _ => return Err(String::from("invalid operands for LD")),
+            }
+        };
+
+        Ok(u16::to_be_bytes(opcode).to_vec())
+    }
+
+    fn vx(operand: &str) -> Result<u16, String> {
+        register(operand)
+            .map(|x| x << 8)
+            .ok_or_else(|| format!("expected a register, found '{operand}'"))
+    }
+
+    fn vy(operand: &str) -> Result<u16, String> {
+        register(operand)
+            .map(|y| y << 4)
+            .ok_or_else(|| format!("expected a register, found '{operand}'"))
+    }
+
+    fn nibble(&self, operand: &str) -> Result<u16, String> {
+        self.value(operand, 0xF)
+    }
+
+    fn address(&self, operand: &str) -> Result<u16, String> {
+        self.value(operand, 0xFFF)
+    }
+
+    // Negative bytes are stored in two's complement, e.g. `ADD V0, -1`
+    fn byte(&self, operand: &str) -> Result<u8, String> {
+        let value = self.evaluate(operand, 0)?;
+
+        if !(-128..=0xFF).contains(&value) {
+            return Err(format!("'{operand}' does not fit in a byte"));
+        }
+
+        Ok(value as u8)
+    }
+
+    fn value(&self, operand: &str, max: u16) -> Result<u16, String> {
+        let value = self.evaluate(operand, 0)?;
+
+        if !(0..=max as i64).contains(&value) {
+            return Err(format!("'{operand}' is out of range (0 to {max:#X})"));
+        }
+
+        Ok(value as u16)
+    }
+
+    // Numbers, labels and constants added or subtracted, e.g. `sprites + 5`
+    fn evaluate(&self, expression: &str, depth: usize) -> Result<i64, String> {
+        if depth > MAX_CONSTANT_DEPTH {
+            return Err(format!("'{expression}' is defined in terms of itself"));
+        }
+
+        let expression = expression.trim();
+
+        if expression.is_empty() {
+            return Err(String::from("missing operand"));
+        }
+
+        let mut total: i64 = 0;
+        let mut sign = 1;
+        let mut term_start = 0;
+
+        for (index, c) in expression.char_indices().chain([(expression.len(), '+')]) {
+            if c != '+' && c != '-' {
+                continue;
+            }
+
+            let term = expression[term_start..index].trim();
+            term_start = index + 1;
+
+            if term.is_empty() {
+                // A leading sign, e.g. `-1`
+                if index == 0 && c == '-' {
+                    sign = -1;
+                    continue;
+                }
+
+                return Err(format!("invalid expression '{expression}'"));
+            }
+
+            total = self
+                .term(term, depth)?
+                .checked_mul(sign)
+                .and_then(|value| total.checked_add(value))
+                .ok_or_else(|| format!("'{expression}' is out of range"))?;
+
+            sign = if c == '-' { -1 } else { 1 };
+        }
+
+        Ok(total)
+    }
+
+    fn term(&self, term: &str, depth: usize) -> Result<i64, String> {
+        if let Some(value) = parse_number(term) {
+            return Ok(value);
+        }
+
+        if let Some(address) = self.labels.get(term) {
+            return Ok(*address as i64);
+        }
+
+        if let Some(value) = self.constants.get(term) {
+            return self.evaluate(value, depth + 1);
+        }
+
+        if term.starts_with(|c: char| c.is_ascii_digit() || c == '#' || c == '$') {
+            Err(format!("invalid number '{term}'"))
+        } else {
+            Err(format!("undefined label or constant '{term}'"))
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::{fmt, ops::Range, rc::Rc, str::FromStr};
+
+use super::{
+    cache::{self, Op},
+    cpu::{Instruction, CPU},
+    ram::RAM,
+    Chip8Error, Variant,
+};
+
+// Longest run of instructions translated at once, it bounds how far back a write can invalidate
+const MAX_BLOCK_INSTRUCTIONS: usize = 32;
+
+// How frames are run
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Fetches and executes one instruction at a time.
+    #[default]
+    Interpreter,
+    /// Translates the code into blocks of decoded instructions that run without fetching them,
+    /// a block is translated again once the program writes over it. Only frames run with
+    /// `Chip8::run_frame` or `Chip8::run_frame_until` use them, stepping always interprets.
+    Blocks,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 2] = [Backend::Interpreter, Backend::Blocks];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Interpreter => "interpreter",
+            Backend::Blocks => "blocks",
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Backend::ALL
+            .into_iter()
+            .find(|backend| backend.name() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = Backend::ALL.iter().map(|b| b.name()).collect();
+
+                format!(
+                    "Unknown backend '{}', expected one of: {}.",
+                    s.trim(),
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+// Instructions that may not continue with the next one end a block: jumps, skips, waits, exits,
+// and whatever can fault or change the length of the next instruction
+fn ends_block(op: Op) -> bool {
+    matches!(
+        op,
+        Op::Return
+            | Op::Exit
+            | Op::Jump
+            | Op::Call
+            | Op::SkipIfEqualByte
+            | Op::SkipIfNotEqualByte
+            | Op::SkipIfEqual
+            | Op::SkipIfNotEqual
+            | Op::JumpWithOffset
+            | Op::SkipIfKey
+            | Op::SkipIfNotKey
+            | Op::LoadLongIndex
+            | Op::WaitForKey
+            | Op::Unknown
+    )
+}
+
+/// Executes an instruction, with its operands already extracted.
+pub(super) type Handler = fn(&mut CPU, &Instruction) -> Result<(), Chip8Error>;
+
+// An instruction of a block, ready to run without fetching or decoding it
+#[derive(Clone, Copy)]
+pub(super) struct Compiled {
+    pub(super) handler: Handler,
+    pub(super) instruction: Instruction,
+    // Only the instructions writing to memory can make the rest of their block stale
+    pub(super) writes_memory: bool,
+    // Besides the VIP timing running out, only draws and exits end a frame early
+    pub(super) may_end_frame: bool,
+}
+
+impl Compiled {
+    fn new(instruction: Instruction, op: Op) -> Compiled {
+        Compiled {
+            handler: CPU::handler(op),
+            instruction,
+            writes_memory: matches!(op, Op::SaveRange | Op::Bcd | Op::Store),
+            may_end_frame: matches!(op, Op::Draw | Op::Exit),
+        }
+    }
+}
+
+/// Decodes the instructions from `start` up to the end of the block. Only the first one has to
+/// be in RAM, a block running off the end stops before it.
+fn translate(ram: &RAM, variant: Variant, start: usize) -> Result<Rc<[Compiled]>, Chip8Error> {
+    let mut block = vec![];
+
+    for address in (start..).step_by(2).take(MAX_BLOCK_INSTRUCTIONS) {
+        let bytes = match ram.peek(address, 2) {
+            Ok(bytes) => bytes,
+            Err(_) if !block.is_empty() => break,
+            Err(e) => return Err(e),
+        };
+
+        let instruction = CPU::parse_instruction(bytes[0], bytes[1]);
+        let op = cache::decode(variant, &instruction);
+        block.push(Compiled::new(instruction, op));
+
+        if ends_block(op) {
+            break;
+        }
+    }
+
+    Ok(block.into())
+}
+
+// Translated blocks by start address
+pub(super) struct BlockCache {
+    // Left empty until the first block is translated
+    blocks: Vec<Option<Rc<[Compiled]>>>,
+    size: usize,
+    translated: u64,
+}
+
+impl BlockCache {
+    pub(super) fn new(ram_size: usize) -> BlockCache {
+        BlockCache {
+            blocks: vec![],
+            size: ram_size,
+            translated: 0,
+        }
+    }
+
+    /// Blocks translated so far, counting those translated again after a write.
+    pub(super) fn translated(&self) -> u64 {
+        self.translated
+    }
+
+    pub(super) fn get_or_translate(
+        &mut self,
+        ram: &RAM,
+        variant: Variant,
+        start: usize,
+    ) -> Result<&Rc<[Compiled]>, Chip8Error> {
+        if !matches!(self.blocks.get(start), Some(Some(_))) {
+            let block = translate(ram, variant, start)?;
+
+            if self.blocks.is_empty() {
+                self.blocks = vec![None; self.size];
+            }
+
+            self.blocks[start] = Some(block);
+            self.translated += 1;
+        }
+
+        Ok(self.blocks[start].as_ref().unwrap())
+    }
+
+    /// Drops the blocks that may overlap the written bytes.
+    pub(super) fn invalidate(&mut self, written: Range<usize>) {
+        let start = written
+            .start
+            .saturating_sub(2 * MAX_BLOCK_INSTRUCTIONS - 1)
+            .min(self.blocks.len());
+        let end = written.end.min(self.blocks.len());
+
+        // Data is often stored right after the code, the blocks ending before it are kept
+        for address in start..end {
+            if matches!(&self.blocks[address], Some(block) if overlaps(block, address, &written)) {
+                self.blocks[address] = None;
+            }
+        }
+    }
+}
+
+/// True when `written` overlaps the bytes of a block starting at `start`.
+pub(super) fn overlaps(block: &[Compiled], start: usize, written: &Range<usize>) -> bool {
+    written.start < start + 2 * block.len() && start < written.end
+}
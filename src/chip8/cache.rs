@@ -0,0 +1,162 @@
+use std::ops::Range;
+
+use super::{cpu::Instruction, Variant};
+
+// What an opcode does, decoded once so executing it is a single jump
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Op {
+    ScrollDown,         // 00CN
+    ScrollUp,           // 00DN
+    Clear,              // 00E0
+    Return,             // 00EE
+    ScrollRight,        // 00FB
+    ScrollLeft,         // 00FC
+    Exit,               // 00FD
+    LowRes,             // 00FE
+    HighRes,            // 00FF
+    Jump,               // 1NNN
+    Call,               // 2NNN
+    SkipIfEqualByte,    // 3XKK
+    SkipIfNotEqualByte, // 4XKK
+    SkipIfEqual,        // 5XY0
+    SaveRange,          // 5XY2
+    LoadRange,          // 5XY3
+    LoadByte,           // 6XKK
+    AddByte,            // 7XKK
+    Move,               // 8XY0
+    Or,                 // 8XY1
+    And,                // 8XY2
+    Xor,                // 8XY3
+    Add,                // 8XY4
+    Sub,                // 8XY5
+    ShiftRight,         // 8XY6
+    SubReversed,        // 8XY7
+    ShiftLeft,          // 8XYE
+    SkipIfNotEqual,     // 9XY0
+    LoadIndex,          // ANNN
+    JumpWithOffset,     // BNNN
+    Random,             // CXKK
+    Draw,               // DXYN
+    SkipIfKey,          // EX9E
+    SkipIfNotKey,       // EXA1
+    LoadLongIndex,      // F000 NNNN
+    SelectPlanes,       // FN01
+    LoadAudio,          // F002
+    GetDelayTimer,      // FX07
+    WaitForKey,         // FX0A
+    SetDelayTimer,      // FX15
+    SetSoundTimer,      // FX18
+    AddIndex,           // FX1E
+    Font,               // FX29
+    BigFont,            // FX30
+    Bcd,                // FX33
+    Store,              // FX55
+    Load,               // FX65
+    SetPitch,           // FX3A
+    SaveFlags,          // FX75
+    LoadFlags,          // FX85
+    /// Not an instruction of the variant, or a 0NNN machine code call.
+    Unknown,
+}
+
+pub(super) fn decode(variant: Variant, instruction: &Instruction) -> Op {
+    let superchip = variant.supports_superchip();
+    let xochip = variant.supports_xochip();
+
+    match instruction.nibbles {
+        (0x0, 0x0, 0xC, _) if superchip => Op::ScrollDown,
+        (0x0, 0x0, 0xD, _) if xochip => Op::ScrollUp,
+        (0x0, 0x0, 0xE, 0x0) => Op::Clear,
+        (0x0, 0x0, 0xE, 0xE) => Op::Return,
+        (0x0, 0x0, 0xF, 0xB) if superchip => Op::ScrollRight,
+        (0x0, 0x0, 0xF, 0xC) if superchip => Op::ScrollLeft,
+        (0x0, 0x0, 0xF, 0xD) if superchip => Op::Exit,
+        (0x0, 0x0, 0xF, 0xE) if superchip => Op::LowRes,
+        (0x0, 0x0, 0xF, 0xF) if superchip => Op::HighRes,
+        (0x1, _, _, _) => Op::Jump,
+        (0x2, _, _, _) => Op::Call,
+        (0x3, _, _, _) => Op::SkipIfEqualByte,
+        (0x4, _, _, _) => Op::SkipIfNotEqualByte,
+        (0x5, _, _, 0x0) => Op::SkipIfEqual,
+        (0x5, _, _, 0x2) if xochip => Op::SaveRange,
+        (0x5, _, _, 0x3) if xochip => Op::LoadRange,
+        (0x6, _, _, _) => Op::LoadByte,
+        (0x7, _, _, _) => Op::AddByte,
+        (0x8, _, _, 0x0) => Op::Move,
+        (0x8, _, _, 0x1) => Op::Or,
+        (0x8, _, _, 0x2) => Op::And,
+        (0x8, _, _, 0x3) => Op::Xor,
+        (0x8, _, _, 0x4) => Op::Add,
+        (0x8, _, _, 0x5) => Op::Sub,
+        (0x8, _, _, 0x6) => Op::ShiftRight,
+        (0x8, _, _, 0x7) => Op::SubReversed,
+        (0x8, _, _, 0xE) => Op::ShiftLeft,
+        (0x9, _, _, 0x0) => Op::SkipIfNotEqual,
+        (0xA, _, _, _) => Op::LoadIndex,
+        (0xB, _, _, _) => Op::JumpWithOffset,
+        (0xC, _, _, _) => Op::Random,
+        (0xD, _, _, _) => Op::Draw,
+        (0xE, _, 0x9, 0xE) => Op::SkipIfKey,
+        (0xE, _, 0xA, 0x1) => Op::SkipIfNotKey,
+        (0xF, 0x0, 0x0, 0x0) if xochip => Op::LoadLongIndex,
+        (0xF, _, 0x0, 0x1) if xochip => Op::SelectPlanes,
+        (0xF, 0x0, 0x0, 0x2) if xochip => Op::LoadAudio,
+        (0xF, _, 0x0, 0x7) => Op::GetDelayTimer,
+        (0xF, _, 0x0, 0xA) => Op::WaitForKey,
+        (0xF, _, 0x1, 0x5) => Op::SetDelayTimer,
+        (0xF, _, 0x1, 0x8) => Op::SetSoundTimer,
+        (0xF, _, 0x1, 0xE) => Op::AddIndex,
+        (0xF, _, 0x2, 0x9) => Op::Font,
+        (0xF, _, 0x3, 0x0) if superchip => Op::BigFont,
+        (0xF, _, 0x3, 0x3) => Op::Bcd,
+        (0xF, _, 0x5, 0x5) => Op::Store,
+        (0xF, _, 0x6, 0x5) => Op::Load,
+        (0xF, _, 0x3, 0xA) if xochip => Op::SetPitch,
+        (0xF, _, 0x7, 0x5) if superchip => Op::SaveFlags,
+        (0xF, _, 0x8, 0x5) if superchip => Op::LoadFlags,
+        _ => Op::Unknown,
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Decoded {
+    pub(super) instruction: Instruction,
+    pub(super) op: Op,
+}
+
+// Instructions decoded so far by address. Programs rarely modify their own code, an instruction
+// is only decoded again once the RAM it was read from is written to.
+pub(super) struct InstructionCache {
+    // Left empty until the first instruction is decoded, scratch machines never allocate it
+    entries: Vec<Option<Decoded>>,
+    size: usize,
+}
+
+impl InstructionCache {
+    pub(super) fn new(ram_size: usize) -> InstructionCache {
+        InstructionCache {
+            entries: vec![],
+            size: ram_size,
+        }
+    }
+
+    pub(super) fn get(&self, address: usize) -> Option<Decoded> {
+        self.entries.get(address).copied().flatten()
+    }
+
+    pub(super) fn insert(&mut self, address: usize, decoded: Decoded) {
+        if self.entries.is_empty() {
+            self.entries = vec![None; self.size];
+        }
+
+        self.entries[address] = Some(decoded);
+    }
+
+    /// Drops the instructions overlapping the written bytes, including one starting just before.
+    pub(super) fn invalidate(&mut self, written: Range<usize>) {
+        let start = written.start.saturating_sub(1).min(self.entries.len());
+        let end = written.end.min(self.entries.len());
+
+        self.entries[start..end].fill(None);
+    }
+}
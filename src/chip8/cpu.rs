@@ -1,72 +1,361 @@
+use std::{ops::Range, rc::Rc};
+
 use super::{
-    devices::{self, Keypad},
-    ram, Position, FONT_DATA,
+    blocks::{self, Backend, BlockCache, Compiled, Handler},
+    cache::{self, Decoded, InstructionCache, Op},
+    ram,
+    rng::Rng,
+    state::{StateReader, StateWriter},
+    timing::{self, Timing},
+    Chip8Error, FaultPolicy, Framebuffer, Keypad, Position, Quirks, Variant, WatchHit, Watchpoint,
+    BIG_FONT_DATA, FONT_DATA,
 };
 
-use rand::prelude::*;
-
-use sdl2::Sdl;
-use std::{error::Error, fs};
-
 const FONT_STARTING_ADDRESS: usize = 0x000;
-const PROGRAM_STARTING_ADDRESS: usize = 0x200;
-
-#[derive(Debug)]
-struct Instruction {
-    kk: u8,
-    nnn: u16,
-    x: usize,
-    y: usize,
-    nibbles: (u8, u8, u8, u8),
+const BIG_FONT_STARTING_ADDRESS: usize = 0x050;
+pub const PROGRAM_STARTING_ADDRESS: usize = 0x200;
+
+// XO-CHIP plays the audio pattern at 4000Hz when the pitch is 64
+const DEFAULT_PITCH: u8 = 64;
+
+// Return addresses of nested subroutine calls, stack[..sp] are in use
+pub const STACK_SIZE: usize = 16;
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Instruction {
+    pub(super) opcode: u16,
+    pub(super) kk: u8,
+    pub(super) nnn: u16,
+    pub(super) x: usize,
+    pub(super) y: usize,
+    pub(super) nibbles: (u8, u8, u8, u8),
 }
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct CPU {
-    delay_timer: u8,
-    display: devices::Display,
-    i: u16,
-    pc: u16,
-    ram: ram::RAM,
-    rng: ThreadRng,
-    sound_timer: u8,
-    sp: u8,
-    speaker: devices::Speaker,
-    stack: [u16; 16],
-    v: [u8; 16],
+    pub(super) audio_pattern: Option<[u8; 16]>,
+    pub(super) backend: Backend,
+    pub(super) blocks: BlockCache,
+    cache: InstructionCache,
+    pub(super) cache_enabled: bool,
+    // Machine cycles left in the current frame with the VIP timing, negative when the last
+    // instruction overran the frame
+    pub(super) cycles: i64,
+    pub(super) delay_timer: u8,
+    pub(super) fault_policy: FaultPolicy,
+    pub(super) framebuffer: Framebuffer,
+    pub(super) halted: bool,
+    pub(super) i: u16,
+    pub(super) instruction_count: u64,
+    pub(super) keypad: Keypad,
+    pub(super) pc: u16,
+    pub(super) pitch: u8,
+    pub(super) quirks: Quirks,
+    pub(super) ram: ram::RAM,
+    pub(super) rng: Rng,
+    pub(super) rpl_flags: [u8; 16],
+    pub(super) sound_timer: u8,
+    pub(super) sp: u8,
+    pub(super) stack: [u16; STACK_SIZE],
+    pub(super) strict: bool,
+    pub(super) timing: Timing,
+    pub(super) v: [u8; 16],
+    pub(super) variant: Variant,
+    triggered_watchpoint: Option<Watchpoint>,
+    waiting_for_vblank: bool,
+    pub(super) watch_hit: Option<WatchHit>,
+    watchpoints: Vec<Watchpoint>,
 }
 
 impl CPU {
-    pub fn new(sdl_context: &Sdl) -> Result<CPU, Box<dyn Error>> {
-        let display = devices::Display::new(sdl_context)?;
-        let ram = ram::RAM::new();
-        let speaker = devices::Speaker::new(sdl_context)?;
-
-        let rng = rand::thread_rng();
-
-        Ok(CPU {
+    pub fn new(variant: Variant) -> CPU {
+        let framebuffer = Framebuffer::new();
+        let keypad = Keypad::new();
+        let ram = ram::RAM::new(variant.ram_size());
+
+        let rng = Rng::new();
+
+        CPU {
+            audio_pattern: None,
+            backend: Backend::default(),
+            blocks: BlockCache::new(variant.ram_size()),
+            cache: InstructionCache::new(variant.ram_size()),
+            cache_enabled: true,
+            cycles: 0,
             delay_timer: 0,
-            display,
+            fault_policy: FaultPolicy::default(),
+            framebuffer,
+            halted: false,
             i: 0,
+            instruction_count: 0,
+            keypad,
             pc: PROGRAM_STARTING_ADDRESS as u16,
+            pitch: DEFAULT_PITCH,
+            quirks: Quirks::default(),
             ram,
             rng,
+            rpl_flags: [0; 16],
             sound_timer: 0,
             sp: 0,
-            speaker,
-            stack: [0; 16],
+            stack: [0; STACK_SIZE],
+            strict: false,
+            timing: Timing::default(),
             v: [0; 16],
-        })
+            variant,
+            triggered_watchpoint: None,
+            waiting_for_vblank: false,
+            watch_hit: None,
+            watchpoints: vec![],
+        }
+    }
+
+    pub fn tick(&mut self, instructions_per_frame: usize) -> Result<(), Chip8Error> {
+        self.start_frame();
+
+        match self.backend {
+            Backend::Interpreter => {
+                for _ in 0..instructions_per_frame {
+                    self.step()?;
+
+                    if self.is_frame_over() {
+                        break;
+                    }
+                }
+            }
+            Backend::Blocks => self.run_blocks(instructions_per_frame)?,
+        }
+
+        self.tick_timers();
+
+        Ok(())
+    }
+
+    pub fn start_frame(&mut self) {
+        self.waiting_for_vblank = false;
+
+        // Cycles left over are lost while waiting for the interrupt, an overrun is paid back
+        if self.timing == Timing::CosmacVip {
+            self.cycles = self.cycles.min(0) + timing::VIP_INTERPRETER_CYCLES;
+        }
+    }
+
+    // No more instructions run in the current frame after a halt, a draw waiting for the display
+    // or once the cycles of the frame are spent
+    pub fn is_frame_over(&self) -> bool {
+        self.waiting_for_vblank
+            || self.halted
+            || (self.timing == Timing::CosmacVip && self.cycles <= 0)
     }
 
-    pub fn tick(
+    pub fn step(&mut self) -> Result<(), Chip8Error> {
+        if self.halted {
+            return Ok(());
+        }
+
+        let address = self.pc;
+
+        let decoded = self
+            .fetch_instruction()
+            .map_err(|e| e.at_instruction(address, None, self.return_addresses()))?;
+
+        self.run_instruction(address, CPU::handler(decoded.op), &decoded.instruction)
+    }
+
+    // Runs up to `instructions` instructions from translated blocks, the same ones `step` would.
+    // The cache is moved out meanwhile, so the running block is borrowed from it instead of cloned
+    fn run_blocks(&mut self, instructions: usize) -> Result<(), Chip8Error> {
+        let mut blocks = std::mem::replace(&mut self.blocks, BlockCache::new(0));
+        let result = self.run_translated(&mut blocks, instructions);
+        self.blocks = blocks;
+
+        result
+    }
+
+    // Only the instructions that can end the frame or write over their block are checked for it,
+    // and without watchpoints or the VIP timing counting an instruction is all that's left to do
+    fn run_translated(
         &mut self,
-        keypad: &Keypad,
-        instructions_per_frame: usize,
-    ) -> Result<bool, Box<dyn Error>> {
-        for _ in 0..instructions_per_frame {
-            let instruction = self.read_instruction()?;
-            self.execute_instruction(&instruction, keypad)?;
+        blocks: &mut BlockCache,
+        mut instructions: usize,
+    ) -> Result<(), Chip8Error> {
+        let vip_timing = self.timing == Timing::CosmacVip;
+        let watching =
+            self.watch_hit.is_some() || !self.watchpoints.is_empty() || self.ram.has_watchpoints();
+
+        while instructions > 0 && !self.halted {
+            if let Some(written) = self.ram.take_written() {
+                self.cache.invalidate(written.clone());
+                blocks.invalidate(written);
+            }
+
+            let start = self.pc as usize;
+            let block = blocks
+                .get_or_translate(&self.ram, self.variant, start)
+                .map_err(|e| e.at_instruction(self.pc, None, self.return_addresses()))?;
+
+            // A loop fitting in the block runs again without looking it up
+            'block: loop {
+                for compiled in block.iter().take(instructions) {
+                    let address = self.pc;
+                    self.pc = self.pc.wrapping_add(2);
+
+                    self.execute_instruction(address, compiled.handler, &compiled.instruction)?;
+                    instructions -= 1;
+
+                    if vip_timing || watching {
+                        self.count_instruction(address, &compiled.instruction);
+                    } else {
+                        self.instruction_count += 1;
+                    }
+
+                    if (compiled.may_end_frame || vip_timing) && self.is_frame_over() {
+                        return Ok(());
+                    }
+
+                    if compiled.writes_memory
+                        && matches!(self.ram.written(), Some(written) if blocks::overlaps(block, start, &written))
+                    {
+                        break 'block;
+                    }
+                }
+
+                if instructions == 0 || self.pc as usize != start {
+                    break;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The translated block starting at the PC, along with its start address.
+    pub fn current_block(&mut self) -> Result<(usize, Rc<[Compiled]>), Chip8Error> {
+        self.invalidate_written();
+
+        let start = self.pc as usize;
+        let block = self
+            .blocks
+            .get_or_translate(&self.ram, self.variant, start)
+            .map(Rc::clone)
+            .map_err(|e| e.at_instruction(self.pc, None, self.return_addresses()))?;
+
+        Ok((start, block))
+    }
+
+    /// Runs the next instruction of `block`, which starts at `start`. False once the rest of the
+    /// block is stale because the program wrote over it.
+    pub fn run_block_instruction(
+        &mut self,
+        block: &[Compiled],
+        start: usize,
+        compiled: &Compiled,
+    ) -> Result<bool, Chip8Error> {
+        let address = self.pc;
+        self.pc = self.pc.wrapping_add(2);
+
+        self.run_instruction(address, compiled.handler, &compiled.instruction)?;
+
+        Ok(!self.is_stale(block, start))
+    }
+
+    // True when the program wrote over the block starting at `start`
+    fn is_stale(&mut self, block: &[Compiled], start: usize) -> bool {
+        matches!(self.invalidate_written(), Some(written) if blocks::overlaps(block, start, &written))
+    }
+
+    // Executes an instruction fetched from `address`, the PC already points past it
+    fn run_instruction(
+        &mut self,
+        address: u16,
+        handler: Handler,
+        instruction: &Instruction,
+    ) -> Result<(), Chip8Error> {
+        self.execute_instruction(address, handler, instruction)?;
+        self.count_instruction(address, instruction);
+
+        Ok(())
+    }
+
+    fn execute_instruction(
+        &mut self,
+        address: u16,
+        handler: Handler,
+        instruction: &Instruction,
+    ) -> Result<(), Chip8Error> {
+        handler(self, instruction).map_err(|e| {
+            // The machine stops on the faulting instruction so it can be inspected
+            self.pc = address;
+            self.halted = true;
+
+            e.at_instruction(address, Some(instruction.opcode), self.return_addresses())
+        })
+    }
+
+    // Counts the instruction executed from `address`, with its cycles and the watchpoints it hit
+    fn count_instruction(&mut self, address: u16, instruction: &Instruction) {
+        self.instruction_count += 1;
+
+        if self.timing == Timing::CosmacVip {
+            // The sprite is drawn after the interrupt, in the cycles of the next frame
+            if self.waiting_for_vblank {
+                self.cycles = 0;
+            }
+
+            let skipped = self.pc != address.wrapping_add(2);
+            self.cycles -= timing::vip_cycles(instruction, skipped);
+        }
+
+        // Registers are checked as they are written, memory as it is accessed
+        if self.triggered_watchpoint.is_some()
+            || self.watch_hit.is_some()
+            || self.ram.has_watch_hit()
+        {
+            let triggered_watchpoint = self.triggered_watchpoint.take();
+            let memory_watchpoint = self.ram.take_watch_hit();
+
+            self.watch_hit = triggered_watchpoint
+                .or(memory_watchpoint)
+                .map(|watchpoint| WatchHit {
+                    watchpoint,
+                    address,
+                });
+        }
+    }
+
+    // The stack levels in use, for fault reports
+    fn return_addresses(&self) -> &[u16] {
+        &self.stack[..self.sp as usize]
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        match watchpoint {
+            Watchpoint::MemoryRead(_) | Watchpoint::MemoryWrite(_) => {
+                self.ram.add_watchpoint(watchpoint)
+            }
+            _ if !self.watchpoints.contains(&watchpoint) => self.watchpoints.push(watchpoint),
+            _ => (),
+        }
+    }
+
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        if self.ram.remove_watchpoint(watchpoint) {
+            return true;
         }
 
+        let count = self.watchpoints.len();
+        self.watchpoints.retain(|w| w != watchpoint);
+
+        self.watchpoints.len() != count
+    }
+
+    pub fn watchpoints(&self) -> Vec<Watchpoint> {
+        self.ram
+            .watchpoints()
+            .chain(self.watchpoints.iter().copied())
+            .collect()
+    }
+
+    pub fn tick_timers(&mut self) {
         if self.delay_timer > 0 {
             self.delay_timer -= 1;
         }
@@ -74,63 +363,131 @@ impl CPU {
         if self.sound_timer > 0 {
             self.sound_timer -= 1;
         }
+    }
 
-        if self.sound_timer > 0 {
-            self.speaker.start_beep();
-        } else {
-            self.speaker.stop_beep();
+    // Everything the program can observe, settings like quirks and watchpoints are left alone
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.audio_pattern.is_some());
+        writer.write_bytes(&self.audio_pattern.unwrap_or_default());
+        writer.write_u64(self.cycles as u64);
+        writer.write_u8(self.delay_timer);
+        self.framebuffer.save_state(writer);
+        writer.write_bool(self.halted);
+        writer.write_u16(self.i);
+        writer.write_u64(self.instruction_count);
+        self.keypad.save_state(writer);
+        writer.write_u16(self.pc);
+        writer.write_u8(self.pitch);
+        self.ram.save_state(writer);
+        writer.write_u64(self.rng.state());
+        writer.write_bytes(&self.rpl_flags);
+        writer.write_u8(self.sound_timer);
+        writer.write_u8(self.sp);
+
+        for address in self.stack {
+            writer.write_u16(address);
         }
 
-        self.display.refresh()?;
+        writer.write_bytes(&self.v);
+        writer.write_bool(self.waiting_for_vblank);
+    }
 
-        Ok(false)
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), String> {
+        let has_audio_pattern = reader.read_bool()?;
+        let audio_pattern = reader.read_array()?;
+        self.audio_pattern = has_audio_pattern.then_some(audio_pattern);
+        self.cycles = reader.read_u64()? as i64;
+        self.delay_timer = reader.read_u8()?;
+        self.framebuffer.load_state(reader)?;
+        self.halted = reader.read_bool()?;
+        self.i = reader.read_u16()?;
+        self.instruction_count = reader.read_u64()?;
+        self.keypad.load_state(reader)?;
+        self.pc = reader.read_u16()?;
+        self.pitch = reader.read_u8()?;
+        self.ram.load_state(reader)?;
+        self.rng = Rng::from_state(reader.read_u64()?);
+        self.rpl_flags = reader.read_array()?;
+        self.sound_timer = reader.read_u8()?;
+        self.sp = reader.read_u8()?;
+
+        if self.sp as usize > STACK_SIZE {
+            return Err(format!(
+                "Invalid stack pointer {} in the save state.",
+                self.sp
+            ));
+        }
+
+        for address in &mut self.stack {
+            *address = reader.read_u16()?;
+        }
+
+        self.v = reader.read_array()?;
+        self.waiting_for_vblank = reader.read_bool()?;
+
+        self.watch_hit = None;
+
+        Ok(())
     }
 
-    pub fn load_font_in_ram(&mut self) -> Result<(), Box<dyn Error>> {
-        self.load_in_ram(FONT_STARTING_ADDRESS, &FONT_DATA)?;
+    pub fn load_font_in_ram(&mut self) -> Result<(), Chip8Error> {
+        self.ram.write(FONT_STARTING_ADDRESS, &FONT_DATA)?;
+        self.ram.write(BIG_FONT_STARTING_ADDRESS, &BIG_FONT_DATA)?;
 
         Ok(())
     }
 
-    pub fn load_program_in_ram(&mut self, file_path: &str) -> Result<(), Box<dyn Error>> {
-        let bytes = fs::read(file_path)?;
+    pub fn load_program_in_ram(&mut self, program: &[u8]) -> Result<usize, Chip8Error> {
+        let capacity = self.ram.size() - PROGRAM_STARTING_ADDRESS;
 
-        println!("Read {} bytes from {}.", bytes.len(), file_path);
+        if program.len() > capacity {
+            return Err(Chip8Error::RomTooLarge {
+                size: program.len(),
+                capacity,
+            });
+        }
 
-        self.load_in_ram(PROGRAM_STARTING_ADDRESS, &bytes)?;
+        self.ram.write(PROGRAM_STARTING_ADDRESS, program)
+    }
 
-        Ok(())
+    // The program may have overwritten instructions that were already decoded
+    fn invalidate_written(&mut self) -> Option<Range<usize>> {
+        let written = self.ram.take_written()?;
+        self.cache.invalidate(written.clone());
+        self.blocks.invalidate(written.clone());
+
+        Some(written)
     }
 
-    fn load_in_ram(&mut self, address: usize, data: &[u8]) -> Result<(), Box<dyn Error>> {
-        let bytes_loaded = self.ram.write(address, data)?;
+    fn fetch_instruction(&mut self) -> Result<Decoded, Chip8Error> {
+        self.invalidate_written();
 
-        println!(
-            "Loaded {} bytes in RAM at address {:#04X?}.",
-            bytes_loaded, address
-        );
+        let address = self.pc as usize;
 
-        Ok(())
-    }
+        let decoded = match self.cache.get(address) {
+            Some(decoded) if self.cache_enabled => decoded,
+            _ => {
+                let bytes = self.ram.peek(address, 2)?;
+                let instruction = CPU::parse_instruction(bytes[0], bytes[1]);
+                let decoded = Decoded {
+                    instruction,
+                    op: cache::decode(self.variant, &instruction),
+                };
 
-    fn read_instruction(&mut self) -> Result<Instruction, String> {
-        let bytes = self.ram.read(self.pc.into(), 2)?;
+                if self.cache_enabled {
+                    self.cache.insert(address, decoded);
+                }
 
-        if bytes.len() != 2 {
-            return Err(format!(
-                "Tried to read 2 bytes at address {:#04X?} but got {} byte(s). {:#04X?}",
-                self.pc,
-                bytes.len(),
-                bytes
-            ));
-        }
+                decoded
+            }
+        };
 
-        self.pc += 2;
+        self.pc = self.pc.wrapping_add(2);
 
-        Ok(CPU::parse_instruction(bytes[0], bytes[1]))
+        Ok(decoded)
     }
 
-    fn parse_instruction(high: u8, low: u8) -> Instruction {
+    pub(super) fn parse_instruction(high: u8, low: u8) -> Instruction {
         let nnn: u16 = (low as u16) | (((high as u16) << 8) & 0x0FFF);
 
         let nibbles = (
@@ -141,6 +498,7 @@ impl CPU {
         );
 
         Instruction {
+            opcode: u16::from_be_bytes([high, low]),
             kk: low,
             nnn,
             x: nibbles.1 as usize,
@@ -149,233 +507,563 @@ impl CPU {
         }
     }
 
-    fn execute_instruction(
-        &mut self,
-        instruction: &Instruction,
-        keypad: &Keypad,
-    ) -> Result<(), Box<dyn Error>> {
-        match instruction.nibbles {
-            (0x0, 0x0, 0xE, 0x0) => self.inst_00e0(),
-            (0x0, 0x0, 0xE, 0xE) => self.inst_00ee(),
-            (0x1, _, _, _) => self.inst_1nnn(instruction),
-            (0x2, _, _, _) => self.inst_2nnn(instruction),
-            (0x3, _, _, _) => self.inst_3xkk(instruction),
-            (0x4, _, _, _) => self.inst_4xkk(instruction),
-            (0x5, _, _, 0x0) => self.inst_5xy0(instruction),
-            (0x6, _, _, _) => self.inst_6xkk(instruction),
-            (0x7, _, _, _) => self.inst_7xkk(instruction),
-            (0x8, _, _, 0x0) => self.inst_8xy0(instruction),
-            (0x8, _, _, 0x1) => self.inst_8xy1(instruction),
-            (0x8, _, _, 0x2) => self.inst_8xy2(instruction),
-            (0x8, _, _, 0x3) => self.inst_8xy3(instruction),
-            (0x8, _, _, 0x4) => self.inst_8xy4(instruction),
-            (0x8, _, _, 0x5) => self.inst_8xy5(instruction),
-            (0x8, _, _, 0x6) => self.inst_8xy6(instruction),
-            (0x8, _, _, 0x7) => self.inst_8xy7(instruction),
-            (0x8, _, _, 0xE) => self.inst_8xye(instruction),
-            (0x9, _, _, 0x0) => self.inst_9xy0(instruction),
-            (0xA, _, _, _) => self.inst_annn(instruction),
-            (0xB, _, _, _) => self.inst_bnnn(instruction),
-            (0xC, _, _, _) => self.inst_cxkk(instruction),
-            (0xD, _, _, _) => self.inst_dxyn(instruction)?,
-            (0xE, _, 0x9, 0xE) => self.inst_ex9e(instruction, keypad)?,
-            (0xE, _, 0xA, 0x1) => self.inst_exa1(instruction, keypad)?,
-            (0xF, _, 0x0, 0x7) => self.inst_fx07(instruction),
-            (0xF, _, 0x0, 0xA) => self.inst_fx0a(instruction, keypad)?,
-            (0xF, _, 0x1, 0x5) => self.inst_fx15(instruction),
-            (0xF, _, 0x1, 0x8) => self.inst_fx18(instruction),
-            (0xF, _, 0x1, 0xE) => self.inst_fx1e(instruction),
-            (0xF, _, 0x2, 0xE) => self.inst_fx2e(instruction),
-            (0xF, _, 0x3, 0x3) => self.inst_fx33(instruction)?,
-            (0xF, _, 0x5, 0x5) => self.inst_fx55(instruction)?,
-            (0xF, _, 0x6, 0x5) => self.inst_fx65(instruction)?,
-            _ => (),
+    fn set_v(&mut self, index: usize, value: u8) {
+        self.v[index] = value;
+
+        if self
+            .watchpoints
+            .contains(&Watchpoint::Register { index, value })
+        {
+            self.triggered_watchpoint = Some(Watchpoint::Register { index, value });
+        }
+    }
+
+    fn set_i(&mut self, value: u16) {
+        self.i = value;
+
+        for watchpoint in &self.watchpoints {
+            if let Watchpoint::IndexInRange { start, end } = watchpoint {
+                if (*start..=*end).contains(&value) {
+                    self.triggered_watchpoint = Some(*watchpoint);
+                }
+            }
+        }
+    }
+
+    fn skip_next_instruction(&mut self) -> Result<(), Chip8Error> {
+        // F000 NNNN is the only instruction taking 4 bytes, it has to be skipped entirely
+        let skipped_bytes = if self.variant.supports_xochip()
+            && self.ram.peek(self.pc as usize, 2)? == [0xF0, 0x00]
+        {
+            4
+        } else {
+            2
         };
 
+        self.pc = self.pc.wrapping_add(skipped_bytes);
+
         Ok(())
     }
 
+    /// The function executing `op`. Blocks look it up once when they are translated, so running
+    /// their instructions doesn't go through the decoding again.
+    pub(super) fn handler(op: Op) -> Handler {
+        match op {
+            Op::ScrollDown => |cpu, i| {
+                cpu.inst_00cn(i);
+                Ok(())
+            },
+            Op::ScrollUp => |cpu, i| {
+                cpu.inst_00dn(i);
+                Ok(())
+            },
+            Op::Clear => |cpu, _| {
+                cpu.inst_00e0();
+                Ok(())
+            },
+            Op::Return => |cpu, _| cpu.inst_00ee(),
+            Op::ScrollRight => |cpu, _| {
+                cpu.inst_00fb();
+                Ok(())
+            },
+            Op::ScrollLeft => |cpu, _| {
+                cpu.inst_00fc();
+                Ok(())
+            },
+            Op::Exit => |cpu, _| {
+                cpu.inst_00fd();
+                Ok(())
+            },
+            Op::LowRes => |cpu, _| {
+                cpu.inst_00fe();
+                Ok(())
+            },
+            Op::HighRes => |cpu, _| {
+                cpu.inst_00ff();
+                Ok(())
+            },
+            Op::Jump => |cpu, i| {
+                cpu.inst_1nnn(i);
+                Ok(())
+            },
+            Op::Call => |cpu, i| cpu.inst_2nnn(i),
+            Op::SkipIfEqualByte => |cpu, i| cpu.inst_3xkk(i),
+            Op::SkipIfNotEqualByte => |cpu, i| cpu.inst_4xkk(i),
+            Op::SkipIfEqual => |cpu, i| cpu.inst_5xy0(i),
+            Op::SaveRange => |cpu, i| cpu.inst_5xy2(i),
+            Op::LoadRange => |cpu, i| cpu.inst_5xy3(i),
+            Op::LoadByte => |cpu, i| {
+                cpu.inst_6xkk(i);
+                Ok(())
+            },
+            Op::AddByte => |cpu, i| {
+                cpu.inst_7xkk(i);
+                Ok(())
+            },
+            Op::Move => |cpu, i| {
+                cpu.inst_8xy0(i);
+                Ok(())
+            },
+            Op::Or => |cpu, i| {
+                cpu.inst_8xy1(i);
+                Ok(())
+            },
+            Op::And => |cpu, i| {
+                cpu.inst_8xy2(i);
+                Ok(())
+            },
+            Op::Xor => |cpu, i| {
+                cpu.inst_8xy3(i);
+                Ok(())
+            },
+            Op::Add => |cpu, i| {
+                cpu.inst_8xy4(i);
+                Ok(())
+            },
+            Op::Sub => |cpu, i| {
+                cpu.inst_8xy5(i);
+                Ok(())
+            },
+            Op::ShiftRight => |cpu, i| {
+                cpu.inst_8xy6(i);
+                Ok(())
+            },
+            Op::SubReversed => |cpu, i| {
+                cpu.inst_8xy7(i);
+                Ok(())
+            },
+            Op::ShiftLeft => |cpu, i| {
+                cpu.inst_8xye(i);
+                Ok(())
+            },
+            Op::SkipIfNotEqual => |cpu, i| cpu.inst_9xy0(i),
+            Op::LoadIndex => |cpu, i| {
+                cpu.inst_annn(i);
+                Ok(())
+            },
+            Op::JumpWithOffset => |cpu, i| {
+                cpu.inst_bnnn(i);
+                Ok(())
+            },
+            Op::Random => |cpu, i| {
+                cpu.inst_cxkk(i);
+                Ok(())
+            },
+            Op::Draw => |cpu, i| cpu.inst_dxyn(i),
+            Op::SkipIfKey => |cpu, i| cpu.inst_ex9e(i),
+            Op::SkipIfNotKey => |cpu, i| cpu.inst_exa1(i),
+            Op::LoadLongIndex => |cpu, _| cpu.inst_f000_nnnn(),
+            Op::SelectPlanes => |cpu, i| {
+                cpu.inst_fn01(i);
+                Ok(())
+            },
+            Op::LoadAudio => |cpu, _| cpu.inst_f002(),
+            Op::GetDelayTimer => |cpu, i| {
+                cpu.inst_fx07(i);
+                Ok(())
+            },
+            Op::WaitForKey => |cpu, i| cpu.inst_fx0a(i),
+            Op::SetDelayTimer => |cpu, i| {
+                cpu.inst_fx15(i);
+                Ok(())
+            },
+            Op::SetSoundTimer => |cpu, i| {
+                cpu.inst_fx18(i);
+                Ok(())
+            },
+            Op::AddIndex => |cpu, i| {
+                cpu.inst_fx1e(i);
+                Ok(())
+            },
+            Op::Font => |cpu, i| {
+                cpu.inst_fx29(i);
+                Ok(())
+            },
+            Op::BigFont => |cpu, i| {
+                cpu.inst_fx30(i);
+                Ok(())
+            },
+            Op::Bcd => |cpu, i| cpu.inst_fx33(i),
+            Op::Store => |cpu, i| cpu.inst_fx55(i),
+            Op::Load => |cpu, i| cpu.inst_fx65(i),
+            Op::SetPitch => |cpu, i| {
+                cpu.inst_fx3a(i);
+                Ok(())
+            },
+            Op::SaveFlags => |cpu, i| {
+                cpu.inst_fx75(i);
+                Ok(())
+            },
+            Op::LoadFlags => |cpu, i| {
+                cpu.inst_fx85(i);
+                Ok(())
+            },
+            // 0NNN calls machine code of the original computer, interpreters skip it
+            Op::Unknown => |cpu, _| {
+                if cpu.strict {
+                    return Err(Chip8Error::InvalidOpcode {
+                        pc: None,
+                        opcode: None,
+                        stack: vec![],
+                    });
+                }
+
+                Ok(())
+            },
+        }
+    }
+
+    fn inst_00cn(&mut self, instruction: &Instruction) {
+        self.framebuffer.scroll_down(instruction.nibbles.3 as usize);
+    }
+
+    fn inst_00dn(&mut self, instruction: &Instruction) {
+        self.framebuffer.scroll_up(instruction.nibbles.3 as usize);
+    }
+
     fn inst_00e0(&mut self) {
-        self.display.clear();
+        self.framebuffer.clear();
     }
 
-    fn inst_00ee(&mut self) {
-        self.pc = self.stack[self.sp as usize];
+    fn inst_00ee(&mut self) -> Result<(), Chip8Error> {
+        if self.sp == 0 {
+            match self.fault_policy {
+                FaultPolicy::Halt => {
+                    return Err(Chip8Error::StackUnderflow {
+                        pc: None,
+                        opcode: None,
+                        stack: vec![],
+                    })
+                }
+                FaultPolicy::Ignore => return Ok(()),
+                FaultPolicy::Wrap => self.sp = STACK_SIZE as u8,
+            }
+        }
+
         self.sp -= 1;
+        self.pc = self.stack[self.sp as usize];
+
+        Ok(())
+    }
+
+    fn inst_00fb(&mut self) {
+        self.framebuffer.scroll_right(4);
+    }
+
+    fn inst_00fc(&mut self) {
+        self.framebuffer.scroll_left(4);
+    }
+
+    fn inst_00fd(&mut self) {
+        self.halted = true;
+    }
+
+    fn inst_00fe(&mut self) {
+        self.framebuffer.set_hires(false);
+    }
+
+    fn inst_00ff(&mut self) {
+        self.framebuffer.set_hires(true);
     }
 
     fn inst_1nnn(&mut self, instruction: &Instruction) {
         self.pc = instruction.nnn;
     }
 
-    fn inst_2nnn(&mut self, instruction: &Instruction) {
-        self.sp += 1;
+    fn inst_2nnn(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
+        if self.sp as usize == STACK_SIZE {
+            match self.fault_policy {
+                FaultPolicy::Halt => {
+                    return Err(Chip8Error::StackOverflow {
+                        pc: None,
+                        opcode: None,
+                        stack: vec![],
+                    })
+                }
+                FaultPolicy::Ignore => return Ok(()),
+                FaultPolicy::Wrap => self.sp = 0,
+            }
+        }
+
         self.stack[self.sp as usize] = self.pc;
+        self.sp += 1;
 
         self.pc = instruction.nnn;
+
+        Ok(())
     }
 
-    fn inst_3xkk(&mut self, instruction: &Instruction) {
+    fn inst_3xkk(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
         if self.v[instruction.x] == instruction.kk {
-            self.pc += 2;
+            self.skip_next_instruction()?;
         }
+
+        Ok(())
     }
 
-    fn inst_4xkk(&mut self, instruction: &Instruction) {
+    fn inst_4xkk(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
         if self.v[instruction.x] != instruction.kk {
-            self.pc += 2;
+            self.skip_next_instruction()?;
         }
+
+        Ok(())
     }
 
-    fn inst_5xy0(&mut self, instruction: &Instruction) {
+    fn inst_5xy0(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
         if self.v[instruction.x] == self.v[instruction.y] {
-            self.pc += 2;
+            self.skip_next_instruction()?;
         }
+
+        Ok(())
+    }
+
+    fn inst_5xy2(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
+        // Registers are stored in reverse order when X is greater than Y
+        let data: Vec<u8> = if instruction.x <= instruction.y {
+            self.v[instruction.x..=instruction.y].to_vec()
+        } else {
+            self.v[instruction.y..=instruction.x]
+                .iter()
+                .rev()
+                .copied()
+                .collect()
+        };
+
+        self.ram.write(self.i as usize, &data)?;
+
+        Ok(())
+    }
+
+    fn inst_5xy3(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
+        let count = instruction.x.abs_diff(instruction.y) + 1;
+
+        let bytes = self.ram.read(self.i as usize, count)?.to_vec();
+
+        for (offset, byte) in bytes.iter().enumerate() {
+            let register_index = if instruction.x <= instruction.y {
+                instruction.x + offset
+            } else {
+                instruction.x - offset
+            };
+
+            self.set_v(register_index, *byte);
+        }
+
+        Ok(())
     }
 
     fn inst_6xkk(&mut self, instruction: &Instruction) {
-        self.v[instruction.x] = instruction.kk;
+        self.set_v(instruction.x, instruction.kk);
     }
 
     fn inst_7xkk(&mut self, instruction: &Instruction) {
-        self.v[instruction.x] = self.v[instruction.x].wrapping_add(instruction.kk);
+        self.set_v(
+            instruction.x,
+            self.v[instruction.x].wrapping_add(instruction.kk),
+        );
     }
 
     fn inst_8xy0(&mut self, instruction: &Instruction) {
-        self.v[instruction.x] = self.v[instruction.y]
+        self.set_v(instruction.x, self.v[instruction.y]);
     }
 
     fn inst_8xy1(&mut self, instruction: &Instruction) {
-        self.v[instruction.x] |= self.v[instruction.y]
+        self.set_v(instruction.x, self.v[instruction.x] | self.v[instruction.y]);
+
+        if self.quirks.vf_reset {
+            self.set_v(0xF, 0);
+        }
     }
 
     fn inst_8xy2(&mut self, instruction: &Instruction) {
-        self.v[instruction.x] &= self.v[instruction.y]
+        self.set_v(instruction.x, self.v[instruction.x] & self.v[instruction.y]);
+
+        if self.quirks.vf_reset {
+            self.set_v(0xF, 0);
+        }
     }
 
     fn inst_8xy3(&mut self, instruction: &Instruction) {
-        self.v[instruction.x] ^= self.v[instruction.y]
+        self.set_v(instruction.x, self.v[instruction.x] ^ self.v[instruction.y]);
+
+        if self.quirks.vf_reset {
+            self.set_v(0xF, 0);
+        }
     }
 
+    // The flag is written after the result, so it's what VF holds when it's also the destination
     fn inst_8xy4(&mut self, instruction: &Instruction) {
-        let result: u16 = self.v[instruction.x] as u16 + self.v[instruction.y] as u16;
+        let (result, carry) = self.v[instruction.x].overflowing_add(self.v[instruction.y]);
 
-        self.v[0xF] = if result > 255 { 1 } else { 0 };
-
-        self.v[instruction.x] = result as u8;
+        self.set_v(instruction.x, result);
+        self.set_v(0xF, if carry { 1 } else { 0 });
     }
 
     fn inst_8xy5(&mut self, instruction: &Instruction) {
-        self.v[0xF] = if self.v[instruction.x] > self.v[instruction.y] {
-            1
-        } else {
-            0
-        };
+        let (result, borrow) = self.v[instruction.x].overflowing_sub(self.v[instruction.y]);
 
-        self.v[instruction.x] = self.v[instruction.x].wrapping_sub(self.v[instruction.y]);
+        self.set_v(instruction.x, result);
+        self.set_v(0xF, if borrow { 0 } else { 1 });
     }
 
     fn inst_8xy6(&mut self, instruction: &Instruction) {
-        self.v[0xF] = if self.v[instruction.x] & 0x01 != 0 {
-            1
+        let value = if self.quirks.shift_uses_vy {
+            self.v[instruction.y]
         } else {
-            0
+            self.v[instruction.x]
         };
 
-        self.v[instruction.x] = self.v[instruction.x].wrapping_div(2);
+        self.set_v(instruction.x, value >> 1);
+        self.set_v(0xF, value & 0x01);
     }
 
     fn inst_8xy7(&mut self, instruction: &Instruction) {
-        self.v[0xF] = if self.v[instruction.y] > self.v[instruction.x] {
-            1
-        } else {
-            0
-        };
+        let (result, borrow) = self.v[instruction.y].overflowing_sub(self.v[instruction.x]);
 
-        self.v[instruction.x] = self.v[instruction.y].wrapping_sub(self.v[instruction.x]);
+        self.set_v(instruction.x, result);
+        self.set_v(0xF, if borrow { 0 } else { 1 });
     }
 
     fn inst_8xye(&mut self, instruction: &Instruction) {
-        self.v[0xF] = if self.v[instruction.x] & 0x80 != 0 {
-            1
+        let value = if self.quirks.shift_uses_vy {
+            self.v[instruction.y]
         } else {
-            0
+            self.v[instruction.x]
         };
 
-        self.v[instruction.x] = self.v[instruction.x].wrapping_mul(2);
+        self.set_v(instruction.x, value << 1);
+        self.set_v(0xF, value >> 7);
     }
 
-    fn inst_9xy0(&mut self, instruction: &Instruction) {
+    fn inst_9xy0(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
         if self.v[instruction.x] != self.v[instruction.y] {
-            self.pc += 2;
+            self.skip_next_instruction()?;
         }
+
+        Ok(())
     }
 
     fn inst_annn(&mut self, instruction: &Instruction) {
-        self.i = instruction.nnn;
+        self.set_i(instruction.nnn);
     }
 
     fn inst_bnnn(&mut self, instruction: &Instruction) {
-        self.pc = instruction.nnn + (self.v[0] as u16);
+        let offset_register = if self.quirks.jump_uses_vx {
+            instruction.x
+        } else {
+            0
+        };
+
+        self.pc = instruction.nnn + (self.v[offset_register] as u16);
     }
 
     fn inst_cxkk(&mut self, instruction: &Instruction) {
-        let random_byte: u8 = self.rng.gen_range(0..=255);
+        let random_byte: u8 = self.rng.next_byte();
 
-        self.v[instruction.x] = random_byte & instruction.kk;
+        self.set_v(instruction.x, random_byte & instruction.kk);
     }
 
-    fn inst_dxyn(&mut self, instruction: &Instruction) -> Result<(), String> {
-        let sprite = self
-            .ram
-            .read(self.i as usize, instruction.nibbles.3 as usize)?;
+    fn inst_dxyn(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
+        // DXY0 draws a 16x16 sprite made of 32 bytes
+        let (sprite_length, bytes_per_row) = match instruction.nibbles.3 {
+            0 if self.variant.supports_superchip() => (32, 2),
+            n => (n as usize, 1),
+        };
 
         let position = Position::new(
             self.v[instruction.x] as usize,
             self.v[instruction.y] as usize,
         );
 
-        let collided = self.display.draw_sprite(sprite, position)?;
+        let mut collided = false;
+        let mut address = self.i as usize;
+
+        // XO-CHIP draws one sprite per selected plane, stored one after the other
+        for plane in [0b01, 0b10] {
+            if self.framebuffer.selected_planes() & plane == 0 {
+                continue;
+            }
+
+            let sprite = self.ram.read(address, sprite_length)?;
+
+            collided |= self.framebuffer.draw_sprite(
+                sprite,
+                bytes_per_row,
+                position,
+                self.quirks.clip_sprites,
+                plane,
+            );
+
+            address += sprite_length;
+        }
+
+        self.set_v(0xF, if collided { 1 } else { 0 });
 
-        self.v[0xF] = if collided { 1 } else { 0 };
+        if self.quirks.display_wait || self.timing == Timing::CosmacVip {
+            self.waiting_for_vblank = true;
+        }
 
         Ok(())
     }
 
-    fn inst_ex9e(&mut self, instruction: &Instruction, keypad: &Keypad) -> Result<(), String> {
+    fn inst_ex9e(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
         let key = self.v[instruction.x];
 
-        if keypad.is_key_pressed(key)? {
-            self.pc += 2;
+        if self.keypad.is_key_pressed(key)? {
+            self.skip_next_instruction()?;
         }
 
         Ok(())
     }
 
-    fn inst_exa1(&mut self, instruction: &Instruction, keypad: &Keypad) -> Result<(), String> {
+    fn inst_exa1(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
         let key = self.v[instruction.x];
 
-        if !keypad.is_key_pressed(key)? {
-            self.pc += 2;
+        if !self.keypad.is_key_pressed(key)? {
+            self.skip_next_instruction()?;
         }
 
         Ok(())
     }
 
+    fn inst_f000_nnnn(&mut self) -> Result<(), Chip8Error> {
+        let bytes = self.ram.peek(self.pc as usize, 2)?;
+
+        self.set_i(u16::from_be_bytes([bytes[0], bytes[1]]));
+        self.pc = self.pc.wrapping_add(2);
+
+        Ok(())
+    }
+
+    fn inst_fn01(&mut self, instruction: &Instruction) {
+        self.framebuffer.select_planes(instruction.nibbles.1);
+    }
+
+    fn inst_f002(&mut self) -> Result<(), Chip8Error> {
+        let bytes = self.ram.read(self.i as usize, 16)?;
+
+        let mut pattern = [0; 16];
+        pattern.copy_from_slice(bytes);
+
+        self.audio_pattern = Some(pattern);
+
+        Ok(())
+    }
+
     fn inst_fx07(&mut self, instruction: &Instruction) {
-        self.v[instruction.x] = self.delay_timer;
+        self.set_v(instruction.x, self.delay_timer);
     }
 
-    fn inst_fx0a(&mut self, instruction: &Instruction, keypad: &Keypad) -> Result<(), String> {
+    fn inst_fx0a(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
         for key in 0x0u8..=0xF {
-            if keypad.is_key_pressed(key)? {
-                self.v[instruction.x] = key;
-                break; // Break out of loop if key is pressed so we move on to next instruction
+            if self.keypad.is_key_pressed(key)? {
+                self.set_v(instruction.x, key);
+                return Ok(()); // Move on to the next instruction once a key is pressed
             }
         }
 
         // Keep re-reading instruction until a key is press
-        self.pc -= 2;
+        self.pc = self.pc.wrapping_sub(2);
 
         Ok(())
     }
@@ -389,14 +1077,22 @@ impl CPU {
     }
 
     fn inst_fx1e(&mut self, instruction: &Instruction) {
-        self.i += self.v[instruction.x] as u16
+        self.set_i(self.i.wrapping_add(self.v[instruction.x] as u16));
     }
 
-    fn inst_fx2e(&mut self, instruction: &Instruction) {
-        self.i = self.v[instruction.x] as u16 * 5
+    fn inst_fx29(&mut self, instruction: &Instruction) {
+        self.set_i(self.v[instruction.x] as u16 * 5);
     }
 
-    fn inst_fx33(&mut self, instruction: &Instruction) -> Result<(), String> {
+    fn inst_fx30(&mut self, instruction: &Instruction) {
+        self.set_i((BIG_FONT_STARTING_ADDRESS + self.v[instruction.x] as usize * 10) as u16);
+    }
+
+    fn inst_fx3a(&mut self, instruction: &Instruction) {
+        self.pitch = self.v[instruction.x];
+    }
+
+    fn inst_fx33(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
         // Decimal to BCD
         let vx = self.v[instruction.x];
 
@@ -411,7 +1107,7 @@ impl CPU {
         Ok(())
     }
 
-    fn inst_fx55(&mut self, instruction: &Instruction) -> Result<(), String> {
+    fn inst_fx55(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
         let mut data: Vec<u8> = vec![];
         for register_index in 0x0..=instruction.x {
             data.push(self.v[register_index]);
@@ -419,28 +1115,36 @@ impl CPU {
 
         self.ram.write(self.i as usize, &data)?;
 
+        if self.quirks.load_store_increments_i {
+            self.set_i(self.i.wrapping_add(instruction.x as u16 + 1));
+        }
+
         Ok(())
     }
 
-    fn inst_fx65(&mut self, instruction: &Instruction) -> Result<(), String> {
+    fn inst_fx65(&mut self, instruction: &Instruction) -> Result<(), Chip8Error> {
         let bytes_to_read = instruction.x + 1;
 
-        let bytes = self.ram.read(self.i as usize, bytes_to_read)?;
+        let bytes = self.ram.read(self.i as usize, bytes_to_read)?.to_vec();
 
-        if bytes.len() != bytes_to_read {
-            return Err(format!(
-                "Tried to read {} bytes at address {:#04X?} but got {} byte(s). {:#04X?}",
-                bytes_to_read,
-                self.i,
-                bytes.len(),
-                bytes
-            ));
+        for (byte_index, byte) in bytes.iter().enumerate() {
+            self.set_v(byte_index, *byte);
         }
 
-        for (byte_index, byte) in bytes.iter().enumerate() {
-            self.v[byte_index] = byte.clone();
+        if self.quirks.load_store_increments_i {
+            self.set_i(self.i.wrapping_add(bytes_to_read as u16));
         }
 
         Ok(())
     }
+
+    fn inst_fx75(&mut self, instruction: &Instruction) {
+        self.rpl_flags[..=instruction.x].copy_from_slice(&self.v[..=instruction.x]);
+    }
+
+    fn inst_fx85(&mut self, instruction: &Instruction) {
+        for register_index in 0..=instruction.x {
+            self.set_v(register_index, self.rpl_flags[register_index]);
+        }
+    }
 }
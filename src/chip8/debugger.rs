@@ -0,0 +1,385 @@
+use std::{
+    collections::BTreeSet,
+    io::{self, BufRead, Write},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use super::{disasm, Chip8, Chip8Error, History, Watchpoint};
+
+const HELP: &str = "\
+Commands:
+  c, continue        resume execution
+  s, step [count]    execute one instruction, or count instructions
+  sb, back [count]   go back one instruction, or count instructions
+  b, break <addr>    add a breakpoint on the PC, e.g. b 0x2A0
+  d, delete <addr>   remove a breakpoint
+  w, watch <addr>    stop when the program writes to an address
+  rw <addr>          stop when the program reads an address
+  wv <reg> <value>   stop when a register gets a value, e.g. wv V3 2A
+  wi <start> <end>   stop when I is set inside a range of addresses
+  dw <number>        remove a watchpoint, numbered as in the list
+  l, list            list breakpoints and watchpoints
+  r, regs            show registers, stack and timers
+  h, help            show this help
+  q, quit            exit the emulator";
+
+enum Command {
+    Continue,
+    Step(usize),
+    StepBack(usize),
+    Break(u16),
+    Delete(u16),
+    Watch(Watchpoint),
+    DeleteWatch(usize),
+    List,
+    Registers,
+    Help,
+    Quit,
+}
+
+// Reads commands from stdin on its own thread so the window keeps refreshing while paused
+pub struct Debugger {
+    breakpoints: BTreeSet<u16>,
+    commands: Option<Receiver<String>>,
+    paused: bool,
+    skip_breakpoint: bool,
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Debugger {
+            breakpoints: BTreeSet::new(),
+            commands: Some(receiver),
+            paused: false,
+            skip_breakpoint: false,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Pausing before the program starts only takes effect once the emulator runs
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn pause(&mut self, chip8: &Chip8) {
+        self.paused = true;
+
+        Debugger::print_state(chip8);
+        Debugger::print_prompt();
+    }
+
+    // Called before every instruction while running, the instruction a breakpoint was hit on
+    // must run once execution resumes
+    pub fn should_break(&mut self, chip8: &Chip8) -> bool {
+        if self.skip_breakpoint {
+            self.skip_breakpoint = false;
+            return false;
+        }
+
+        if let Some(watch_hit) = chip8.watch_hit() {
+            println!("Watchpoint hit: {watch_hit}.");
+            return true;
+        }
+
+        if self.breakpoints.contains(&chip8.pc()) {
+            println!("Breakpoint hit at {:#06X}.", chip8.pc());
+            return true;
+        }
+
+        false
+    }
+
+    // Runs the commands typed since the last call, returns true when the user wants to quit. The
+    // history is what stepping back rewinds to.
+    pub fn process_commands(
+        &mut self,
+        chip8: &mut Chip8,
+        history: &mut History,
+    ) -> Result<bool, Chip8Error> {
+        loop {
+            let Some(commands) = &self.commands else {
+                return Ok(false);
+            };
+
+            let line = match commands.try_recv() {
+                Ok(line) => line,
+                Err(TryRecvError::Empty) => return Ok(false),
+                Err(TryRecvError::Disconnected) => {
+                    // Nobody can type commands anymore, let the program run
+                    println!("Debugger input closed, resuming execution.");
+                    self.commands = None;
+                    self.paused = false;
+                    return Ok(false);
+                }
+            };
+
+            match Debugger::parse_command(&line) {
+                Ok(Some(command)) => {
+                    if self.execute_command(command, chip8, history)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(None) => (),
+                Err(e) => println!("{e}"),
+            }
+
+            if self.paused {
+                Debugger::print_prompt();
+            }
+        }
+    }
+
+    fn execute_command(
+        &mut self,
+        command: Command,
+        chip8: &mut Chip8,
+        history: &mut History,
+    ) -> Result<bool, Chip8Error> {
+        match command {
+            Command::Continue => {
+                self.paused = false;
+                self.skip_breakpoint = true;
+            }
+            Command::Step(count) => {
+                for _ in 0..count {
+                    if let Err(e) = chip8.step() {
+                        println!("{e}");
+                        break;
+                    }
+
+                    if let Some(watch_hit) = chip8.watch_hit() {
+                        println!("Watchpoint hit: {watch_hit}.");
+                        break;
+                    }
+                }
+
+                Debugger::print_state(chip8);
+            }
+            Command::StepBack(count) => {
+                Debugger::step_back(chip8, history, count)?;
+                Debugger::print_state(chip8);
+            }
+            Command::Break(address) => {
+                self.breakpoints.insert(address);
+                println!("Breakpoint added at {:#06X}.", address);
+            }
+            Command::Delete(address) => {
+                if self.breakpoints.remove(&address) {
+                    println!("Breakpoint removed at {:#06X}.", address);
+                } else {
+                    println!("No breakpoint at {:#06X}.", address);
+                }
+            }
+            Command::Watch(watchpoint) => {
+                chip8.add_watchpoint(watchpoint);
+                println!("Watchpoint added on {watchpoint}.");
+            }
+            Command::DeleteWatch(number) => match chip8.watchpoints().get(number) {
+                Some(watchpoint) => {
+                    chip8.remove_watchpoint(watchpoint);
+                    println!("Watchpoint removed on {watchpoint}.");
+                }
+                None => println!("No watchpoint number {number}."),
+            },
+            Command::List => {
+                if self.breakpoints.is_empty() {
+                    println!("No breakpoints.");
+                }
+
+                for address in &self.breakpoints {
+                    println!("  {:#06X}", address);
+                }
+
+                let watchpoints = chip8.watchpoints();
+
+                if watchpoints.is_empty() {
+                    println!("No watchpoints.");
+                }
+
+                for (number, watchpoint) in watchpoints.iter().enumerate() {
+                    println!("  {number}: {watchpoint}");
+                }
+            }
+            Command::Registers => Debugger::print_state(chip8),
+            Command::Help => println!("{HELP}"),
+            Command::Quit => return Ok(true),
+        }
+
+        Ok(false)
+    }
+
+    // Snapshots are only taken at the start of frames, going back restores the last one taken
+    // before the target instruction and replays the instructions in between
+    fn step_back(chip8: &mut Chip8, history: &mut History, count: usize) -> Result<(), Chip8Error> {
+        let target = chip8.instruction_count().saturating_sub(count as u64);
+
+        loop {
+            let Some(snapshot) = history.latest() else {
+                println!("No history left to step back into.");
+                return Ok(());
+            };
+
+            chip8.load_state(snapshot)?;
+
+            if chip8.instruction_count() <= target {
+                break;
+            }
+
+            history.pop();
+        }
+
+        while chip8.instruction_count() < target {
+            chip8.step()?;
+        }
+
+        Ok(())
+    }
+
+    fn parse_command(line: &str) -> Result<Option<Command>, String> {
+        let mut words = line.split_whitespace();
+
+        let Some(name) = words.next() else {
+            return Ok(None);
+        };
+
+        let argument = words.next();
+        let second_argument = words.next();
+
+        let command = match name {
+            "c" | "continue" => Command::Continue,
+            "s" | "step" => match argument {
+                Some(count) => Command::Step(
+                    count
+                        .parse()
+                        .map_err(|_| format!("Invalid instruction count '{count}'."))?,
+                ),
+                None => Command::Step(1),
+            },
+            "sb" | "back" => match argument {
+                Some(count) => Command::StepBack(
+                    count
+                        .parse()
+                        .map_err(|_| format!("Invalid instruction count '{count}'."))?,
+                ),
+                None => Command::StepBack(1),
+            },
+            "b" | "break" => Command::Break(Debugger::parse_address(argument)?),
+            "d" | "delete" => Command::Delete(Debugger::parse_address(argument)?),
+            "w" | "watch" => {
+                Command::Watch(Watchpoint::MemoryWrite(Debugger::parse_address(argument)?))
+            }
+            "rw" => Command::Watch(Watchpoint::MemoryRead(Debugger::parse_address(argument)?)),
+            "wv" => {
+                let register = argument.ok_or("Missing register.")?;
+
+                let index = usize::from_str_radix(register.trim_start_matches(['V', 'v']), 16)
+                    .ok()
+                    .filter(|index| *index < 16)
+                    .ok_or_else(|| format!("Invalid register '{register}'."))?;
+
+                let value = u8::try_from(Debugger::parse_address(second_argument)?)
+                    .map_err(|_| String::from("Register values are a single byte."))?;
+
+                Command::Watch(Watchpoint::Register { index, value })
+            }
+            "wi" => Command::Watch(Watchpoint::IndexInRange {
+                start: Debugger::parse_address(argument)?,
+                end: Debugger::parse_address(second_argument)?,
+            }),
+            "dw" => Command::DeleteWatch(
+                argument
+                    .ok_or("Missing watchpoint number.")?
+                    .parse()
+                    .map_err(|_| String::from("Invalid watchpoint number."))?,
+            ),
+            "l" | "list" => Command::List,
+            "r" | "regs" => Command::Registers,
+            "h" | "help" => Command::Help,
+            "q" | "quit" => Command::Quit,
+            _ => return Err(format!("Unknown command '{name}', type 'help' for a list.")),
+        };
+
+        Ok(Some(command))
+    }
+
+    // Addresses and values are always hexadecimal, with or without a 0x, # or $ prefix
+    pub fn parse_address(argument: Option<&str>) -> Result<u16, String> {
+        let argument = argument.ok_or("Missing address.")?;
+
+        let digits = argument
+            .trim_start_matches("0x")
+            .trim_start_matches(['#', '$']);
+
+        u16::from_str_radix(digits, 16).map_err(|_| format!("Invalid address '{argument}'."))
+    }
+
+    fn print_state(chip8: &Chip8) {
+        let opcode = chip8
+            .opcode_at(chip8.pc())
+            .map(|opcode| {
+                let mnemonic = disasm::disassemble_instruction(opcode, chip8.variant());
+                format!("{:04X} {}", opcode, mnemonic)
+            })
+            .unwrap_or_else(|| String::from("????"));
+
+        println!(
+            "PC: {:#06X} [{}]  I: {:#06X}  SP: {:#04X}  DT: {:#04X}  ST: {:#04X}",
+            chip8.pc(),
+            opcode,
+            chip8.i(),
+            chip8.sp(),
+            chip8.delay_timer(),
+            chip8.sound_timer()
+        );
+
+        for (row_index, registers) in chip8.registers().chunks(8).enumerate() {
+            let registers: Vec<String> = registers
+                .iter()
+                .enumerate()
+                .map(|(i, value)| format!("V{:X}: {:#04X}", row_index * 8 + i, value))
+                .collect();
+
+            println!("{}", registers.join("  "));
+        }
+
+        let stack: Vec<String> = chip8
+            .stack()
+            .iter()
+            .take(chip8.sp() as usize)
+            .map(|address| format!("{:#06X}", address))
+            .collect();
+
+        println!("Stack: [{}]", stack.join(", "));
+    }
+
+    fn print_prompt() {
+        print!("(chip-8) ");
+        io::stdout().flush().unwrap_or(());
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Debugger::new()
+    }
+}
@@ -1,7 +1,49 @@
-mod display;
-mod keypad;
-mod speaker;
+use super::{Framebuffer, Palette};
 
-pub use display::Display;
-pub use keypad::Keypad;
-pub use speaker::Speaker;
+pub enum InputEvent {
+    Quit,
+    KeyDown(u8),
+    KeyUp(u8),
+    SpeedUp,
+    SpeedDown,
+    Break,
+    SaveState,
+    LoadState,
+    NextStateSlot,
+    PreviousStateSlot,
+    RewindStart,
+    RewindStop,
+    Screenshot,
+    ToggleScreenRecording,
+}
+
+pub trait Display {
+    fn refresh(&mut self, framebuffer: &Framebuffer) -> Result<(), String>;
+
+    // The colours the screen is drawn in, screenshots and recordings use them too
+    fn palette(&self) -> Palette {
+        Palette::default()
+    }
+
+    // Status messages of the emulator, like a state being saved, go to the terminal unless the
+    // display has a better place for them
+    fn show_message(&mut self, message: &str) {
+        println!("{message}");
+    }
+
+    fn show_error(&mut self, error: &str) {
+        eprintln!("{error}");
+    }
+}
+
+pub trait Speaker {
+    fn start_beep(&mut self);
+    fn stop_beep(&mut self);
+
+    // XO-CHIP programs replace the beep with a 128 bits pattern played at a rate set by the pitch
+    fn set_pattern(&mut self, _pattern: &[u8; 16], _pitch: u8) {}
+}
+
+pub trait Input {
+    fn poll_event(&mut self) -> Option<InputEvent>;
+}
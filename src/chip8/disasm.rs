@@ -0,0 +1,311 @@
+use std::{collections::BTreeSet, fmt};
+
+use super::{
+    cpu::{Instruction, CPU, PROGRAM_STARTING_ADDRESS},
+    Variant,
+};
+
+pub struct DisassembledLine {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    /// Set when the line is the target of a jump or a call.
+    pub label: Option<String>,
+    pub text: String,
+}
+
+pub struct Disassembly {
+    pub lines: Vec<DisassembledLine>,
+}
+
+// Where execution can go after an instruction, used to tell code from data
+enum Flow {
+    Next,
+    Jump(u16),
+    Call(u16),
+    Skip,
+    Stop,
+}
+
+/// Disassembles a ROM loaded at 0x200. Only bytes reachable from 0x200 by following jumps, calls
+/// and skips are decoded as instructions, everything else is shown as data. Targets get a label
+/// when a line starts there, targets outside the ROM or inside another instruction stay numbers.
+pub fn disassemble(rom: &[u8], variant: Variant) -> Disassembly {
+    let code = find_code(rom, variant);
+    let layout = layout(rom, &code, variant);
+
+    let starts: BTreeSet<u16> = layout
+        .iter()
+        .map(|(offset, _)| (PROGRAM_STARTING_ADDRESS + offset) as u16)
+        .collect();
+
+    let targets: BTreeSet<u16> = code
+        .iter()
+        .filter_map(|offset| match decode(rom, *offset, variant) {
+            Some((_, Flow::Jump(target) | Flow::Call(target))) => Some(target),
+            _ => None,
+        })
+        .filter(|target| starts.contains(target))
+        .collect();
+
+    let label = |address: u16| {
+        if targets.contains(&address) {
+            label_name(address)
+        } else {
+            format!("#{:03X}", address)
+        }
+    };
+
+    let mut lines = vec![];
+
+    for (offset, length) in layout {
+        let address = (PROGRAM_STARTING_ADDRESS + offset) as u16;
+
+        let line_label = if targets.contains(&address) {
+            Some(label_name(address))
+        } else {
+            None
+        };
+
+        let decoded = if code.contains(&offset) {
+            decode(rom, offset, variant)
+        } else {
+            None
+        };
+
+        let text = match decoded {
+            Some((instruction, _)) => {
+                let long_operand = long_operand(rom, offset, variant);
+
+                mnemonic(&instruction, long_operand, variant, &label)
+            }
+            None => format!("DB #{:02X}", rom[offset]),
+        };
+
+        lines.push(DisassembledLine {
+            address,
+            bytes: rom[offset..offset + length].to_vec(),
+            label: line_label,
+            text,
+        });
+    }
+
+    Disassembly { lines }
+}
+
+// Offset and length of every line, an instruction where code was found and a byte of data
+// everywhere else
+fn layout(rom: &[u8], code: &BTreeSet<usize>, variant: Variant) -> Vec<(usize, usize)> {
+    let mut layout = vec![];
+    let mut offset = 0;
+
+    while offset < rom.len() {
+        let length = if code.contains(&offset) {
+            instruction_length(rom, offset, variant)
+        } else {
+            1
+        };
+
+        layout.push((offset, length));
+        offset += length;
+    }
+
+    layout
+}
+
+/// A single instruction, with addresses shown as numbers.
+pub fn disassemble_instruction(opcode: u16, variant: Variant) -> String {
+    let [high, low] = opcode.to_be_bytes();
+    let instruction = CPU::parse_instruction(high, low);
+
+    mnemonic(&instruction, None, variant, &|address| {
+        format!("#{:03X}", address)
+    })
+}
+
+fn find_code(rom: &[u8], variant: Variant) -> BTreeSet<usize> {
+    let mut code = BTreeSet::new();
+    let mut pending = vec![0];
+
+    while let Some(offset) = pending.pop() {
+        if offset + 1 >= rom.len() || !code.insert(offset) {
+            continue;
+        }
+
+        let Some((_, flow)) = decode(rom, offset, variant) else {
+            continue;
+        };
+
+        let next = offset + instruction_length(rom, offset, variant);
+
+        match flow {
+            Flow::Next => pending.push(next),
+            Flow::Jump(target) => pending.extend(target_offset(target)),
+            Flow::Call(target) => {
+                pending.push(next);
+                pending.extend(target_offset(target));
+            }
+            Flow::Skip => {
+                pending.push(next);
+                pending.push(next + instruction_length(rom, next, variant));
+            }
+            Flow::Stop => (),
+        }
+    }
+
+    code
+}
+
+fn target_offset(target: u16) -> Option<usize> {
+    (target as usize).checked_sub(PROGRAM_STARTING_ADDRESS)
+}
+
+fn decode(rom: &[u8], offset: usize, variant: Variant) -> Option<(Instruction, Flow)> {
+    if offset + 1 >= rom.len() {
+        return None;
+    }
+
+    let instruction = CPU::parse_instruction(rom[offset], rom[offset + 1]);
+
+    let flow = match instruction.nibbles {
+        (0x0, 0x0, 0xE, 0xE) => Flow::Stop,
+        (0x0, 0x0, 0xF, 0xD) if variant.supports_superchip() => Flow::Stop,
+        (0x1, _, _, _) => Flow::Jump(instruction.nnn),
+        (0x2, _, _, _) => Flow::Call(instruction.nnn),
+        // The target depends on a register, nothing can be followed
+        (0xB, _, _, _) => Flow::Stop,
+        (0x3 | 0x4, _, _, _) | (0x5 | 0x9, _, _, 0x0) => Flow::Skip,
+        (0xE, _, 0x9, 0xE) | (0xE, _, 0xA, 0x1) => Flow::Skip,
+        _ => Flow::Next,
+    };
+
+    Some((instruction, flow))
+}
+
+// F000 NNNN is the only instruction taking 4 bytes
+fn instruction_length(rom: &[u8], offset: usize, variant: Variant) -> usize {
+    if long_operand(rom, offset, variant).is_some() {
+        4
+    } else {
+        2
+    }
+}
+
+fn long_operand(rom: &[u8], offset: usize, variant: Variant) -> Option<u16> {
+    if !variant.supports_xochip() || offset + 3 >= rom.len() {
+        return None;
+    }
+
+    if rom[offset] != 0xF0 || rom[offset + 1] != 0x00 {
+        return None;
+    }
+
+    Some(u16::from_be_bytes([rom[offset + 2], rom[offset + 3]]))
+}
+
+fn label_name(address: u16) -> String {
+    format!("L{:03X}", address)
+}
+
+fn mnemonic(
+    instruction: &Instruction,
+    long_operand: Option<u16>,
+    variant: Variant,
+    label: &dyn Fn(u16) -> String,
+) -> String {
+    let superchip = variant.supports_superchip();
+    let xochip = variant.supports_xochip();
+
+    let (x, y, n) = (instruction.x, instruction.y, instruction.nibbles.3);
+    let (kk, nnn) = (instruction.kk, instruction.nnn);
+
+    match instruction.nibbles {
+        (0x0, 0x0, 0xC, _) if superchip => format!("SCD {n}"),
+        (0x0, 0x0, 0xD, _) if xochip => format!("SCU {n}"),
+        (0x0, 0x0, 0xE, 0x0) => String::from("CLS"),
+        (0x0, 0x0, 0xE, 0xE) => String::from("RET"),
+        (0x0, 0x0, 0xF, 0xB) if superchip => String::from("SCR"),
+        (0x0, 0x0, 0xF, 0xC) if superchip => String::from("SCL"),
+        (0x0, 0x0, 0xF, 0xD) if superchip => String::from("EXIT"),
+        (0x0, 0x0, 0xF, 0xE) if superchip => String::from("LOW"),
+        (0x0, 0x0, 0xF, 0xF) if superchip => String::from("HIGH"),
+        (0x0, _, _, _) => format!("SYS #{:03X}", nnn),
+        (0x1, _, _, _) => format!("JP {}", label(nnn)),
+        (0x2, _, _, _) => format!("CALL {}", label(nnn)),
+        (0x3, _, _, _) => format!("SE V{:X}, #{:02X}", x, kk),
+        (0x4, _, _, _) => format!("SNE V{:X}, #{:02X}", x, kk),
+        (0x5, _, _, 0x0) => format!("SE V{:X}, V{:X}", x, y),
+        (0x5, _, _, 0x2) if xochip => format!("SAVE V{:X}, V{:X}", x, y),
+        (0x5, _, _, 0x3) if xochip => format!("LOAD V{:X}, V{:X}", x, y),
+        (0x6, _, _, _) => format!("LD V{:X}, #{:02X}", x, kk),
+        (0x7, _, _, _) => format!("ADD V{:X}, #{:02X}", x, kk),
+        (0x8, _, _, 0x0) => format!("LD V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x1) => format!("OR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x2) => format!("AND V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x3) => format!("XOR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x4) => format!("ADD V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x5) => format!("SUB V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x6) => format!("SHR V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0x7) => format!("SUBN V{:X}, V{:X}", x, y),
+        (0x8, _, _, 0xE) => format!("SHL V{:X}, V{:X}", x, y),
+        (0x9, _, _, 0x0) => format!("SNE V{:X}, V{:X}", x, y),
+        (0xA, _, _, _) => format!("LD I, #{:03X}", nnn),
+        (0xB, _, _, _) => format!("JP V0, #{:03X}", nnn),
+        (0xC, _, _, _) => format!("RND V{:X}, #{:02X}", x, kk),
+        (0xD, _, _, _) => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+        (0xE, _, 0x9, 0xE) => format!("SKP V{:X}", x),
+        (0xE, _, 0xA, 0x1) => format!("SKNP V{:X}", x),
+        (0xF, 0x0, 0x0, 0x0) if xochip => match long_operand {
+            Some(address) => format!("LD I, LONG #{:04X}", address),
+            // Cut short by the end of the ROM
+            None => String::from("DW #F000"),
+        },
+        (0xF, _, 0x0, 0x1) if xochip => format!("PLANE {}", x),
+        (0xF, 0x0, 0x0, 0x2) if xochip => String::from("AUDIO"),
+        (0xF, _, 0x0, 0x7) => format!("LD V{:X}, DT", x),
+        (0xF, _, 0x0, 0xA) => format!("LD V{:X}, K", x),
+        (0xF, _, 0x1, 0x5) => format!("LD DT, V{:X}", x),
+        (0xF, _, 0x1, 0x8) => format!("LD ST, V{:X}", x),
+        (0xF, _, 0x1, 0xE) => format!("ADD I, V{:X}", x),
+        (0xF, _, 0x2, 0x9) => format!("LD F, V{:X}", x),
+        (0xF, _, 0x3, 0x0) if superchip => format!("LD HF, V{:X}", x),
+        (0xF, _, 0x3, 0x3) => format!("LD B, V{:X}", x),
+        (0xF, _, 0x3, 0xA) if xochip => format!("PITCH V{:X}", x),
+        (0xF, _, 0x5, 0x5) => format!("LD [I], V{:X}", x),
+        (0xF, _, 0x6, 0x5) => format!("LD V{:X}, [I]", x),
+        (0xF, _, 0x7, 0x5) if superchip => format!("LD R, V{:X}", x),
+        (0xF, _, 0x8, 0x5) if superchip => format!("LD V{:X}, R", x),
+        _ => format!(
+            "DW #{:02X}{:02X}",
+            (instruction.nibbles.0 << 4) | instruction.nibbles.1,
+            kk
+        ),
+    }
+}
+
+impl fmt::Display for DisassembledLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(label) = &self.label {
+            writeln!(f, "{label}:")?;
+        }
+
+        let bytes: Vec<String> = self.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+
+        write!(
+            f,
+            "    {:04X}  {:<8}  {}",
+            self.address,
+            bytes.join(""),
+            self.text
+        )
+    }
+}
+
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{line}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,91 +1,523 @@
 use std::{
-    error::Error,
-    thread,
-    time::{Duration, Instant},
+    fs,
+    path::{Path, PathBuf},
 };
 
-use sdl2::{event::Event, keyboard::Keycode, EventPump, Sdl};
+use super::{
+    devices::{Display, Input, InputEvent, Speaker},
+    scheduler::Scheduler,
+    screenshot, Chip8, Chip8Error, Debugger, History, Movie, MovieInput, ScreenRecorder,
+    FRAME_RATE,
+};
 
-use super::{cpu::CPU, devices};
+const STATE_SLOTS: u8 = 10;
 
-const DEFAULT_INSTRUCTIONS_PER_FRAME: usize = 50; // This can vary a lot by programs, usually programs that are well designed should not care, but that's not always the case unfortunately
-const FRAME_TIME_IN_MILLIS: u64 = 17; // 1000 (1 sec in millis) / 60 (fps) = 16.666
+// The speed keys add or remove one instruction per frame
+const CLOCK_RATE_STEP: u32 = FRAME_RATE;
 
-pub struct Emulator<'a> {
-    cpu: CPU,
-    instructions_per_frame: usize,
-    keypad: devices::Keypad,
-    sdl_context: &'a Sdl,
-}
+// How far back holding the rewind key can go
+const REWIND_SECONDS: usize = 10;
 
-impl<'a> Emulator<'a> {
-    pub fn new(instructions_per_frame: Option<usize>, sdl_context: &'a Sdl) -> Emulator<'a> {
-        let instructions_per_frame =
-            instructions_per_frame.unwrap_or(DEFAULT_INSTRUCTIONS_PER_FRAME);
+// A movie being recorded, written to its path once the emulator stops
+struct Recording {
+    movie: Movie,
+    path: PathBuf,
+}
 
-        let keypad = devices::Keypad::new();
+pub struct Emulator<D: Display, S: Speaker, I: Input> {
+    chip8: Chip8,
+    debugger: Option<Debugger>,
+    display: D,
+    frame: u64,
+    history: History,
+    input: I,
+    program_path: PathBuf,
+    recording: Option<Recording>,
+    replay: Option<Movie>,
+    replay_position: usize,
+    rewinding: bool,
+    screen_recorder: Option<ScreenRecorder>,
+    screenshot_scale: usize,
+    speaker: S,
+    startup_state: Option<PathBuf>,
+    state_slot: u8,
+}
 
+impl<D: Display, S: Speaker, I: Input> Emulator<D, S, I> {
+    pub fn new(chip8: Chip8, display: D, speaker: S, input: I) -> Emulator<D, S, I> {
         Emulator {
-            cpu: CPU::new(sdl_context).unwrap(),
-            instructions_per_frame,
-            keypad,
-            sdl_context,
+            chip8,
+            debugger: None,
+            display,
+            frame: 0,
+            history: History::new(REWIND_SECONDS * 60),
+            input,
+            program_path: PathBuf::new(),
+            recording: None,
+            replay: None,
+            replay_position: 0,
+            rewinding: false,
+            screen_recorder: None,
+            screenshot_scale: 1,
+            speaker,
+            startup_state: None,
+            state_slot: 0,
         }
     }
 
-    pub fn run(&mut self, program_path: &str) -> Result<(), Box<dyn Error>> {
-        self.cpu.load_font_in_ram()?;
-        self.cpu.load_program_in_ram(program_path)?;
+    /// A save state loaded right after the program, e.g. one saved with the save state hotkey.
+    pub fn set_startup_state(&mut self, path: impl AsRef<Path>) {
+        self.startup_state = Some(path.as_ref().to_path_buf());
+    }
+
+    /// Screenshots and screen recordings started with the hotkeys draw each pixel as a `scale` by
+    /// `scale` square.
+    pub fn set_screenshot_scale(&mut self, scale: usize) {
+        self.screenshot_scale = scale;
+    }
+
+    pub fn chip8(&self) -> &Chip8 {
+        &self.chip8
+    }
+
+    /// Records every input in a movie written to `path` when the emulator stops. The machine is
+    /// reseeded with `seed`, which the movie keeps along with the quirks and the speed.
+    pub fn record_movie(&mut self, path: impl AsRef<Path>, seed: u64) {
+        self.chip8.set_seed(seed);
+
+        self.recording = Some(Recording {
+            movie: Movie::new(&self.chip8, seed),
+            path: path.as_ref().to_path_buf(),
+        });
+    }
+
+    /// Plays the inputs of a movie instead of the ones from the input device, which takes over
+    /// once the movie ends.
+    pub fn replay_movie(&mut self, movie: Movie) {
+        self.replay = Some(movie);
+    }
+
+    // The debugger pauses on its breakpoints or when the break key is pressed, and is then driven
+    // by commands typed on stdin
+    pub fn set_debugger(&mut self, debugger: Debugger) {
+        self.debugger = Some(debugger);
+    }
+
+    pub fn run(&mut self, program_path: &str) -> Result<(), Chip8Error> {
+        self.start(program_path)?;
+
+        let result = self.run_loop();
 
-        let mut event_pump = self.sdl_context.event_pump().unwrap();
+        self.stop_screen_recording();
+
+        // Even a run that failed is worth keeping, it's how the failure is reproduced
+        if let Some(recording) = &mut self.recording {
+            recording.movie.length = self.frame;
+            fs::write(&recording.path, recording.movie.to_string()).map_err(|source| {
+                Chip8Error::Io {
+                    path: recording.path.clone(),
+                    source,
+                }
+            })?;
+
+            self.display
+                .show_message(&format!("Saved movie to {}.", recording.path.display()));
+        }
+
+        result
+    }
+
+    fn start(&mut self, program_path: &str) -> Result<(), Chip8Error> {
+        let program = fs::read(program_path).map_err(|source| Chip8Error::Io {
+            path: PathBuf::from(program_path),
+            source,
+        })?;
+
+        self.display.show_message(&format!(
+            "Read {} bytes from {}.",
+            program.len(),
+            program_path
+        ));
+
+        self.chip8.load_rom(&program)?;
+        self.program_path = PathBuf::from(program_path);
+
+        if let Some(state_path) = &self.startup_state {
+            let state = fs::read(state_path).map_err(|source| Chip8Error::Io {
+                path: state_path.clone(),
+                source,
+            })?;
+            self.chip8.load_state(&state)?;
+
+            self.display
+                .show_message(&format!("Loaded state from {}.", state_path.display()));
+        }
+
+        if let Some(recording) = &mut self.recording {
+            recording.movie.rom_hash = self.chip8.rom_hash();
+        }
+
+        self.start_replay().map_err(Chip8Error::InvalidMovie)?;
+
+        // The latest snapshot is always where the current frame started
+        self.history.push(self.chip8.save_state());
+
+        if let Some(debugger) = &mut self.debugger {
+            if debugger.is_paused() {
+                debugger.pause(&self.chip8);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_loop(&mut self) -> Result<(), Chip8Error> {
+        let mut scheduler = Scheduler::new();
 
         'running: loop {
-            let start_time = Instant::now();
+            if self.process_events() {
+                break 'running;
+            }
 
-            if self.process_events(&mut event_pump) {
+            if self.run_debugger_commands()? {
                 break 'running;
             }
 
-            self.cpu.tick(&self.keypad, self.instructions_per_frame)?;
+            // Timers and instructions follow the emulated frames, the screen is only drawn once
+            // when several frames are run to catch up
+            for _ in 0..scheduler.frames_due() {
+                if self.rewinding {
+                    self.rewind_frame()?;
+                    self.record_screen();
+                } else if !self.is_paused() {
+                    self.replay_inputs();
+                    self.run_frame()?;
+                    self.frame += 1;
+                    self.history.push(self.chip8.save_state());
+                    self.record_screen();
+                }
 
-            let elapsed_time_in_millis = start_time.elapsed().as_millis();
+                // A fault caught by the debugger halts the machine too, it only exits once resumed
+                if self.chip8.is_halted() && !self.is_paused() {
+                    self.display.show_message("Program exited.");
+                    break 'running;
+                }
+            }
+
+            if let Some((pattern, pitch)) = self.chip8.audio_pattern() {
+                self.speaker.set_pattern(pattern, pitch);
+            }
 
-            if FRAME_TIME_IN_MILLIS as u128 > elapsed_time_in_millis {
-                thread::sleep(Duration::from_millis(
-                    FRAME_TIME_IN_MILLIS - elapsed_time_in_millis as u64,
-                ));
+            if self.chip8.sound_timer() > 0 && !self.is_paused() {
+                self.speaker.start_beep();
+            } else {
+                self.speaker.stop_beep();
             }
+
+            self.display
+                .refresh(self.chip8.framebuffer())
+                .map_err(Chip8Error::Device)?;
+
+            scheduler.sleep_until_next_frame();
+        }
+
+        Ok(())
+    }
+
+    fn start_replay(&mut self) -> Result<(), String> {
+        let Some(movie) = &self.replay else {
+            return Ok(());
+        };
+
+        if movie.rom_hash != self.chip8.rom_hash() {
+            return Err(String::from("The movie was recorded with a different ROM."));
         }
 
+        if movie.variant != self.chip8.variant() {
+            return Err(format!(
+                "The movie was recorded with the {} variant, not {}.",
+                movie.variant,
+                self.chip8.variant()
+            ));
+        }
+
+        self.chip8.set_quirks(movie.quirks);
+        self.chip8.set_seed(movie.seed);
+        self.chip8.set_clock_rate(movie.clock_rate);
+        self.chip8.set_timing(movie.timing);
+        self.chip8.set_fault_policy(movie.fault_policy);
+        self.chip8.set_strict(movie.strict);
+
+        self.display
+            .show_message(&format!("Replaying a movie of {} frames.", movie.length));
+
         Ok(())
     }
 
-    fn process_events(&mut self, event_pump: &mut EventPump) -> bool {
-        for event in event_pump.poll_iter() {
+    // Applies the inputs the movie recorded before the current frame
+    fn replay_inputs(&mut self) {
+        let Some(movie) = &self.replay else {
+            return;
+        };
+
+        if self.frame >= movie.length {
+            self.display.show_message("Replay finished.");
+            self.replay = None;
+            return;
+        }
+
+        while let Some(event) = movie.events.get(self.replay_position) {
+            if event.frame > self.frame {
+                break;
+            }
+
+            apply_input(&mut self.chip8, event.input);
+            self.replay_position += 1;
+        }
+    }
+
+    // Inputs are what movies are made of, the ones from the device are ignored during a replay
+    fn handle_input(&mut self, input: MovieInput) {
+        if self.replay.is_some() {
+            return;
+        }
+
+        apply_input(&mut self.chip8, input);
+
+        if let Some(recording) = &mut self.recording {
+            recording.movie.record(self.frame, input);
+        }
+    }
+
+    // Going back in time would make movies impossible to replay
+    fn is_timeline_locked(&mut self) -> bool {
+        let locked = self.recording.is_some() || self.replay.is_some();
+
+        if locked {
+            self.display.show_message(
+                "Rewinding and loading states are disabled while a movie is recorded or replayed.",
+            );
+        }
+
+        locked
+    }
+
+    fn is_paused(&self) -> bool {
+        self.debugger
+            .as_ref()
+            .is_some_and(|debugger| debugger.is_paused())
+    }
+
+    fn run_frame(&mut self) -> Result<(), Chip8Error> {
+        let Some(debugger) = &mut self.debugger else {
+            return self.chip8.run_frame();
+        };
+
+        match self
+            .chip8
+            .run_frame_until(|chip8| debugger.should_break(chip8))
+        {
+            Ok(false) => (),
+            Ok(true) => debugger.pause(&self.chip8),
+            // The machine halted on the faulting instruction, which is left to inspect
+            Err(e) => {
+                self.display.show_error(&e.to_string());
+                debugger.pause(&self.chip8);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Goes back one frame, the oldest snapshot stays on screen once the history is exhausted
+    fn rewind_frame(&mut self) -> Result<(), Chip8Error> {
+        if self.history.len() > 1 {
+            self.history.pop();
+        }
+
+        if let Some(snapshot) = self.history.latest() {
+            self.chip8.load_state(snapshot)?;
+        }
+
+        Ok(())
+    }
+
+    fn run_debugger_commands(&mut self) -> Result<bool, Chip8Error> {
+        match &mut self.debugger {
+            Some(debugger) => debugger.process_commands(&mut self.chip8, &mut self.history),
+            None => Ok(false),
+        }
+    }
+
+    fn process_events(&mut self) -> bool {
+        while let Some(event) = self.input.poll_event() {
+            let clock_rate = self.chip8.clock_rate();
+
             match event {
-                Event::Quit { .. } => return true,
-                Event::KeyDown { keycode, .. } => {
-                    if let Some(key) = keycode {
-                        self.keypad.press_key(key);
-
-                        match key {
-                            Keycode::PageUp => self.instructions_per_frame += 1,
-                            Keycode::PageDown if self.instructions_per_frame > 1 => {
-                                self.instructions_per_frame -= 1
-                            }
-                            _ => (),
+                InputEvent::Quit => return true,
+                InputEvent::KeyDown(key) => self.handle_input(MovieInput::KeyDown(key)),
+                InputEvent::KeyUp(key) => self.handle_input(MovieInput::KeyUp(key)),
+                InputEvent::SpeedUp => self.handle_input(MovieInput::ClockRate(
+                    clock_rate.saturating_add(CLOCK_RATE_STEP),
+                )),
+                InputEvent::SpeedDown if clock_rate > CLOCK_RATE_STEP => {
+                    self.handle_input(MovieInput::ClockRate(clock_rate - CLOCK_RATE_STEP))
+                }
+                InputEvent::Break => {
+                    if let Some(debugger) = &mut self.debugger {
+                        if !debugger.is_paused() {
+                            debugger.pause(&self.chip8);
                         }
                     }
                 }
-                Event::KeyUp { keycode, .. } => {
-                    if let Some(key) = keycode {
-                        self.keypad.release_key(key);
-                    }
+                InputEvent::SaveState => self.save_state(),
+                InputEvent::LoadState if !self.is_timeline_locked() => self.load_state(),
+                InputEvent::RewindStart if !self.is_timeline_locked() => self.rewinding = true,
+                InputEvent::RewindStop => self.rewinding = false,
+                InputEvent::Screenshot => self.save_screenshot(),
+                InputEvent::ToggleScreenRecording if self.screen_recorder.is_some() => {
+                    self.stop_screen_recording()
                 }
+                InputEvent::ToggleScreenRecording => self.start_screen_recording(),
+                InputEvent::NextStateSlot => self.select_state_slot(1),
+                InputEvent::PreviousStateSlot => self.select_state_slot(STATE_SLOTS - 1),
                 _ => (),
             }
         }
 
         false
     }
+
+    // Slots are files next to the program, programs/game.ch8 saves slot 3 in programs/game.state3
+    fn state_path(&self) -> PathBuf {
+        self.program_path
+            .with_extension(format!("state{}", self.state_slot))
+    }
+
+    // Screenshots are next to the program too, named after the frame: programs/game-600.png
+    fn save_screenshot(&mut self) {
+        let stem = self.program_path.file_stem().unwrap_or_default();
+        let path = self.program_path.with_file_name(format!(
+            "{}-{}.png",
+            stem.to_string_lossy(),
+            self.frame
+        ));
+
+        let result = screenshot::save_screenshot(
+            &path,
+            self.chip8.framebuffer(),
+            &self.display.palette(),
+            self.screenshot_scale,
+        );
+
+        match result {
+            Ok(()) => self
+                .display
+                .show_message(&format!("Saved screenshot to {}.", path.display())),
+            Err(e) => self
+                .display
+                .show_error(&format!("Error while saving screenshot: {e}")),
+        }
+    }
+
+    // Recordings are named like screenshots after the frame they start at: programs/game-600.gif
+    fn start_screen_recording(&mut self) {
+        let stem = self.program_path.file_stem().unwrap_or_default();
+        let path = self.program_path.with_file_name(format!(
+            "{}-{}.gif",
+            stem.to_string_lossy(),
+            self.frame
+        ));
+
+        self.display
+            .show_message(&format!("Recording the screen to {}.", path.display()));
+
+        self.screen_recorder = Some(ScreenRecorder::new(
+            path,
+            self.display.palette(),
+            self.screenshot_scale,
+        ));
+    }
+
+    // A recording that fails is dropped and the program keeps running
+    fn record_screen(&mut self) {
+        let Some(recorder) = &mut self.screen_recorder else {
+            return;
+        };
+
+        if let Err(e) = recorder.add_frame(self.chip8.framebuffer()) {
+            self.display
+                .show_error(&format!("Error while recording the screen: {e}"));
+            self.screen_recorder = None;
+        }
+    }
+
+    fn stop_screen_recording(&mut self) {
+        let Some(recorder) = self.screen_recorder.take() else {
+            return;
+        };
+
+        let (path, frames) = (recorder.path().to_path_buf(), recorder.frames());
+
+        match recorder.finish() {
+            Ok(()) => self
+                .display
+                .show_message(&format!("Saved {frames} frame(s) to {}.", path.display())),
+            Err(e) => self
+                .display
+                .show_error(&format!("Error while saving the screen recording: {e}")),
+        }
+    }
+
+    fn select_state_slot(&mut self, offset: u8) {
+        self.state_slot = (self.state_slot + offset) % STATE_SLOTS;
+
+        self.display
+            .show_message(&format!("Save state slot {}.", self.state_slot));
+    }
+
+    // A failed save or load is reported and the program keeps running
+    fn save_state(&mut self) {
+        let path = self.state_path();
+
+        match fs::write(&path, self.chip8.save_state()) {
+            Ok(()) => self
+                .display
+                .show_message(&format!("Saved state to {}.", path.display())),
+            Err(e) => self.display.show_error(&format!(
+                "Error while saving state to {}: {e}",
+                path.display()
+            )),
+        }
+    }
+
+    fn load_state(&mut self) {
+        let path = self.state_path();
+
+        let result = fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|state| self.chip8.load_state(&state).map_err(|e| e.to_string()));
+
+        match result {
+            Ok(()) => {
+                // The history belongs to the timeline that was just left
+                self.history.clear();
+                self.history.push(self.chip8.save_state());
+                self.display
+                    .show_message(&format!("Loaded state from {}.", path.display()));
+            }
+            Err(e) => self.display.show_error(&format!(
+                "Error while loading state from {}: {e}",
+                path.display()
+            )),
+        }
+    }
+}
+
+fn apply_input(chip8: &mut Chip8, input: MovieInput) {
+    match input {
+        MovieInput::KeyDown(key) => chip8.set_key(key, true),
+        MovieInput::KeyUp(key) => chip8.set_key(key, false),
+        MovieInput::ClockRate(clock_rate) => chip8.set_clock_rate(clock_rate),
+    }
 }
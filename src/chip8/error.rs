@@ -0,0 +1,200 @@
+use std::{error::Error, fmt, io, path::PathBuf};
+
+/// Everything that can go wrong while loading or running a program. Faults raised by an
+/// instruction carry its address and opcode, `None` when the fault happened outside of one, e.g.
+/// when a tool reads RAM, and the return addresses of the subroutines it was in, outermost
+/// first.
+#[derive(Debug)]
+pub enum Chip8Error {
+    /// A read or write past the end of RAM.
+    MemoryOutOfBounds {
+        pc: Option<u16>,
+        opcode: Option<u16>,
+        stack: Vec<u16>,
+        address: usize,
+        length: usize,
+        ram_size: usize,
+    },
+    /// A key outside of the hex keypad, e.g. EX9E with VX above 0xF.
+    InvalidKey {
+        pc: Option<u16>,
+        opcode: Option<u16>,
+        stack: Vec<u16>,
+        key: u8,
+    },
+    /// A 2NNN call with every level of the stack in use.
+    StackOverflow {
+        pc: Option<u16>,
+        opcode: Option<u16>,
+        stack: Vec<u16>,
+    },
+    /// A 00EE return outside of any subroutine.
+    StackUnderflow {
+        pc: Option<u16>,
+        opcode: Option<u16>,
+        stack: Vec<u16>,
+    },
+    /// An opcode the variant doesn't know, or a 0NNN machine code call, only raised in strict
+    /// mode.
+    InvalidOpcode {
+        pc: Option<u16>,
+        opcode: Option<u16>,
+        stack: Vec<u16>,
+    },
+    /// The ROM doesn't fit in RAM after 0x200.
+    RomTooLarge {
+        size: usize,
+        capacity: usize,
+    },
+    InvalidSaveState(String),
+    InvalidMovie(String),
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// A display, speaker or input device failed, e.g. SDL.
+    Device(String),
+}
+
+impl Chip8Error {
+    /// Adds the instruction being executed and the return addresses on the stack to faults that
+    /// don't know them yet.
+    pub fn at_instruction(
+        mut self,
+        instruction_pc: u16,
+        instruction_opcode: Option<u16>,
+        return_addresses: &[u16],
+    ) -> Self {
+        match &mut self {
+            Chip8Error::MemoryOutOfBounds {
+                pc, opcode, stack, ..
+            }
+            | Chip8Error::InvalidKey {
+                pc, opcode, stack, ..
+            }
+            | Chip8Error::StackOverflow { pc, opcode, stack }
+            | Chip8Error::StackUnderflow { pc, opcode, stack }
+            | Chip8Error::InvalidOpcode { pc, opcode, stack } => {
+                if pc.is_none() {
+                    *pc = Some(instruction_pc);
+                    *stack = return_addresses.to_vec();
+                }
+
+                if opcode.is_none() {
+                    *opcode = instruction_opcode;
+                }
+            }
+            _ => (),
+        }
+
+        self
+    }
+
+    /// Address of the instruction that faulted, if an instruction did.
+    pub fn pc(&self) -> Option<u16> {
+        match self {
+            Chip8Error::MemoryOutOfBounds { pc, .. }
+            | Chip8Error::InvalidKey { pc, .. }
+            | Chip8Error::StackOverflow { pc, .. }
+            | Chip8Error::StackUnderflow { pc, .. }
+            | Chip8Error::InvalidOpcode { pc, .. } => *pc,
+            _ => None,
+        }
+    }
+
+    pub fn opcode(&self) -> Option<u16> {
+        match self {
+            Chip8Error::MemoryOutOfBounds { opcode, .. }
+            | Chip8Error::InvalidKey { opcode, .. }
+            | Chip8Error::StackOverflow { opcode, .. }
+            | Chip8Error::StackUnderflow { opcode, .. }
+            | Chip8Error::InvalidOpcode { opcode, .. } => *opcode,
+            _ => None,
+        }
+    }
+
+    /// Return addresses of the subroutines the faulting instruction was in, outermost first.
+    pub fn stack(&self) -> Option<&[u16]> {
+        match self {
+            Chip8Error::MemoryOutOfBounds { pc, stack, .. }
+            | Chip8Error::InvalidKey { pc, stack, .. }
+            | Chip8Error::StackOverflow { pc, stack, .. }
+            | Chip8Error::StackUnderflow { pc, stack, .. }
+            | Chip8Error::InvalidOpcode { pc, stack, .. } => pc.map(|_| stack.as_slice()),
+            _ => None,
+        }
+    }
+
+    fn fmt_instruction(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.pc(), self.opcode()) {
+            (Some(pc), Some(opcode)) => write!(f, " (PC {:#06X}, opcode {:04X})", pc, opcode),
+            (Some(pc), None) => write!(f, " (PC {:#06X})", pc),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Chip8Error::MemoryOutOfBounds {
+                address,
+                length,
+                ram_size,
+                ..
+            } => write!(
+                f,
+                "Accessing {length} byte(s) at {:#06X} exceeds the {ram_size} bytes of RAM",
+                address
+            )?,
+            Chip8Error::InvalidKey { key, .. } => {
+                write!(f, "There is no key {:#04X} on the keypad", key)?
+            }
+            Chip8Error::StackOverflow { stack, .. } => write!(
+                f,
+                "Stack overflow, all {} levels of subroutines are in use",
+                stack.len()
+            )?,
+            Chip8Error::StackUnderflow { .. } => {
+                write!(f, "Stack underflow, returning while not in a subroutine")?
+            }
+            Chip8Error::InvalidOpcode { opcode, .. } => match opcode {
+                Some(opcode) if opcode & 0xF000 == 0 => {
+                    write!(f, "0NNN calls machine code, which can't be emulated")?
+                }
+                _ => write!(f, "Unknown instruction")?,
+            },
+            Chip8Error::RomTooLarge { size, capacity } => write!(
+                f,
+                "The ROM is {size} bytes but only {capacity} bytes fit in RAM"
+            )?,
+            Chip8Error::InvalidSaveState(message) => write!(f, "{message}")?,
+            Chip8Error::InvalidMovie(message) => write!(f, "Invalid movie: {message}")?,
+            Chip8Error::Io { path, source } => write!(f, "{}: {source}", path.display())?,
+            Chip8Error::Device(message) => write!(f, "{message}")?,
+        }
+
+        self.fmt_instruction(f)?;
+
+        // The return addresses tell where the faulting code was called from
+        if let Some(stack) = self.stack() {
+            let stack: Vec<String> = stack
+                .iter()
+                .map(|address| format!("{:#06X}", address))
+                .collect();
+
+            write!(f, "\nStack: [{}]", stack.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for Chip8Error {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Chip8Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
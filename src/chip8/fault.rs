@@ -0,0 +1,50 @@
+use std::{fmt, str::FromStr};
+
+// What happens when a program calls a 17th subroutine or returns with an empty stack
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FaultPolicy {
+    /// Stop the machine and report the fault with the stack at that point.
+    #[default]
+    Halt,
+    /// Skip the call or the return, execution carries on with the next instruction.
+    Ignore,
+    /// The stack pointer wraps around, a call overwrites the oldest return address.
+    Wrap,
+}
+
+impl FaultPolicy {
+    pub const ALL: [FaultPolicy; 3] = [FaultPolicy::Halt, FaultPolicy::Ignore, FaultPolicy::Wrap];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FaultPolicy::Halt => "halt",
+            FaultPolicy::Ignore => "ignore",
+            FaultPolicy::Wrap => "wrap",
+        }
+    }
+}
+
+impl fmt::Display for FaultPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for FaultPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FaultPolicy::ALL
+            .into_iter()
+            .find(|policy| policy.name() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = FaultPolicy::ALL.iter().map(|p| p.name()).collect();
+
+                format!(
+                    "Unknown fault policy '{}', expected one of: {}.",
+                    s.trim(),
+                    names.join(", ")
+                )
+            })
+    }
+}
@@ -0,0 +1,249 @@
+use super::{
+    state::{StateReader, StateWriter},
+    Position,
+};
+
+pub const WIDTH: usize = 64;
+pub const HEIGHT: usize = 32;
+
+// SUPER-CHIP high resolution mode doubles both dimensions
+pub const HIRES_WIDTH: usize = 128;
+pub const HIRES_HEIGHT: usize = 64;
+
+// Each pixel holds one bit per plane, only XO-CHIP programs ever select the second one
+const FIRST_PLANE: u8 = 0b01;
+const ALL_PLANES: u8 = 0b11;
+
+pub struct Framebuffer {
+    hires: bool,
+    pixels: [[u8; HIRES_WIDTH]; HIRES_HEIGHT],
+    selected_planes: u8,
+}
+
+impl Framebuffer {
+    pub fn new() -> Framebuffer {
+        Framebuffer {
+            hires: false,
+            pixels: [[0; HIRES_WIDTH]; HIRES_HEIGHT],
+            selected_planes: FIRST_PLANE,
+        }
+    }
+
+    pub(super) fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_bool(self.hires);
+        writer.write_u8(self.selected_planes);
+
+        for row in &self.pixels {
+            writer.write_bytes(row);
+        }
+    }
+
+    pub(super) fn load_state(&mut self, reader: &mut StateReader) -> Result<(), String> {
+        self.hires = reader.read_bool()?;
+        self.selected_planes = reader.read_u8()? & ALL_PLANES;
+
+        for row in &mut self.pixels {
+            *row = reader.read_array()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn width(&self) -> usize {
+        if self.hires {
+            HIRES_WIDTH
+        } else {
+            WIDTH
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_HEIGHT
+        } else {
+            HEIGHT
+        }
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.pixels.fill([0; HIRES_WIDTH]);
+    }
+
+    pub fn selected_planes(&self) -> u8 {
+        self.selected_planes
+    }
+
+    pub fn select_planes(&mut self, planes: u8) {
+        self.selected_planes = planes & ALL_PLANES;
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        self.color(x, y) != 0
+    }
+
+    // Index in the palette, bit 0 is the first plane and bit 1 the second
+    pub fn color(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y % self.height()][x % self.width()]
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        self.set_color(x, y, if on { FIRST_PLANE } else { 0 });
+    }
+
+    pub fn set_color(&mut self, x: usize, y: usize, color: u8) {
+        let (width, height) = (self.width(), self.height());
+
+        self.pixels[y % height][x % width] = color & ALL_PLANES;
+    }
+
+    pub fn clear(&mut self) {
+        let planes = self.selected_planes;
+
+        self.pixels
+            .iter_mut()
+            .flatten()
+            .for_each(|pixel| *pixel &= !planes);
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        let (width, height) = (self.width(), self.height());
+
+        for row in (0..height).rev() {
+            for column in 0..width {
+                let source = if row >= rows {
+                    self.pixels[row - rows][column]
+                } else {
+                    0
+                };
+
+                self.copy_selected_planes(source, column, row);
+            }
+        }
+    }
+
+    pub fn scroll_up(&mut self, rows: usize) {
+        let (width, height) = (self.width(), self.height());
+
+        for row in 0..height {
+            for column in 0..width {
+                let source = if row + rows < height {
+                    self.pixels[row + rows][column]
+                } else {
+                    0
+                };
+
+                self.copy_selected_planes(source, column, row);
+            }
+        }
+    }
+
+    pub fn scroll_right(&mut self, columns: usize) {
+        let (width, height) = (self.width(), self.height());
+
+        for row in 0..height {
+            for column in (0..width).rev() {
+                let source = if column >= columns {
+                    self.pixels[row][column - columns]
+                } else {
+                    0
+                };
+
+                self.copy_selected_planes(source, column, row);
+            }
+        }
+    }
+
+    pub fn scroll_left(&mut self, columns: usize) {
+        let (width, height) = (self.width(), self.height());
+
+        for row in 0..height {
+            for column in 0..width {
+                let source = if column + columns < width {
+                    self.pixels[row][column + columns]
+                } else {
+                    0
+                };
+
+                self.copy_selected_planes(source, column, row);
+            }
+        }
+    }
+
+    // Sprites are 8 pixels wide, except SUPER-CHIP 16x16 sprites which use 2 bytes per row. Only
+    // the given plane is drawn to, XO-CHIP draws one sprite per selected plane
+    pub fn draw_sprite(
+        &mut self,
+        sprite: &[u8],
+        bytes_per_row: usize,
+        position: Position,
+        clip: bool,
+        plane: u8,
+    ) -> bool {
+        let mut collided = false;
+
+        let (width, height) = (self.width(), self.height());
+
+        // The starting position always wraps, only the parts of the sprite going past the edges
+        // can be clipped
+        let origin_x = position.x % width;
+        let origin_y = position.y % height;
+
+        let masks = [0x80, 0x40, 0x20, 0x10, 0x08, 0x04, 0x02, 0x01];
+
+        for (line_index, line) in sprite.chunks(bytes_per_row).enumerate() {
+            let mut row = origin_y + line_index;
+
+            if row >= height {
+                if clip {
+                    break;
+                }
+
+                row %= height;
+            }
+
+            for (byte_index, byte) in line.iter().enumerate() {
+                for (i, mask) in masks.iter().enumerate() {
+                    let mut column_index = origin_x + byte_index * 8 + i;
+
+                    if column_index >= width {
+                        if clip {
+                            break;
+                        }
+
+                        column_index %= width;
+                    }
+
+                    if byte & mask == 0 {
+                        continue;
+                    }
+
+                    // Sprites are XORed onto the screen, if a pixel has been erased, then there was a collision
+                    if self.pixels[row][column_index] & plane != 0 {
+                        collided = true;
+                    }
+
+                    self.pixels[row][column_index] ^= plane;
+                }
+            }
+        }
+
+        collided
+    }
+
+    fn copy_selected_planes(&mut self, source: u8, x: usize, y: usize) {
+        let planes = self.selected_planes;
+
+        self.pixels[y][x] = (self.pixels[y][x] & !planes) | (source & planes);
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Framebuffer::new()
+    }
+}
@@ -0,0 +1,25 @@
+use crate::chip8::{
+    devices::{Display, Input, InputEvent, Speaker},
+    Framebuffer,
+};
+
+// Does nothing with the output and never produces input, useful to run programs without a window
+pub struct Headless;
+
+impl Display for Headless {
+    fn refresh(&mut self, _framebuffer: &Framebuffer) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+impl Speaker for Headless {
+    fn start_beep(&mut self) {}
+
+    fn stop_beep(&mut self) {}
+}
+
+impl Input for Headless {
+    fn poll_event(&mut self) -> Option<InputEvent> {
+        None
+    }
+}
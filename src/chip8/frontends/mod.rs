@@ -0,0 +1,9 @@
+mod headless;
+
+#[cfg(feature = "sdl")]
+pub mod sdl;
+
+#[cfg(feature = "tty")]
+pub mod tty;
+
+pub use headless::Headless;
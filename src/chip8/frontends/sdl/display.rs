@@ -0,0 +1,87 @@
+use std::error::Error;
+
+use sdl2::{pixels::Color, rect::Rect, render::Canvas, video::Window, Sdl};
+
+use crate::chip8::{devices::Display, Framebuffer, Palette};
+
+pub struct SdlDisplay {
+    canvas: Canvas<Window>,
+    palette: Palette,
+}
+
+impl SdlDisplay {
+    pub fn new(sdl_context: &Sdl, palette: Palette) -> Result<SdlDisplay, Box<dyn Error>> {
+        let video_subsystem = sdl_context.video()?;
+
+        let window = video_subsystem
+            .window("Chip-8-rs", 800, 600)
+            .position_centered()
+            .resizable()
+            .build()?;
+
+        let mut canvas = window.into_canvas().build()?;
+
+        canvas.set_draw_color(SdlDisplay::sdl_color(&palette, 0));
+        canvas.clear();
+        canvas.present();
+
+        Ok(SdlDisplay { canvas, palette })
+    }
+
+    fn sdl_color(palette: &Palette, index: u8) -> Color {
+        let (r, g, b) = palette.color(index);
+
+        Color::RGB(r, g, b)
+    }
+}
+
+impl Display for SdlDisplay {
+    fn refresh(&mut self, framebuffer: &Framebuffer) -> Result<(), String> {
+        self.canvas
+            .set_draw_color(SdlDisplay::sdl_color(&self.palette, 0));
+        self.canvas.clear();
+
+        let (width, height) = (framebuffer.width(), framebuffer.height());
+
+        let window_size = self.canvas.window().size();
+        let rect_width = window_size.0 / width as u32;
+        let rect_height = window_size.1 / height as u32;
+
+        let mut row_index = 0;
+
+        while row_index < height {
+            let y = (row_index as u32) * rect_height;
+            let mut col_index = 0;
+
+            while col_index < width {
+                let color = framebuffer.color(col_index, row_index);
+
+                if color != 0 {
+                    let x = (col_index as u32) * rect_width;
+
+                    self.canvas
+                        .set_draw_color(SdlDisplay::sdl_color(&self.palette, color));
+
+                    self.canvas.fill_rect(Rect::new(
+                        x as i32,
+                        y as i32,
+                        rect_width,
+                        rect_height,
+                    ))?;
+                }
+
+                col_index += 1;
+            }
+
+            row_index += 1;
+        }
+
+        self.canvas.present();
+
+        Ok(())
+    }
+
+    fn palette(&self) -> Palette {
+        self.palette
+    }
+}
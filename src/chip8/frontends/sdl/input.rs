@@ -0,0 +1,78 @@
+use sdl2::{event::Event, keyboard::Keycode, EventPump, Sdl};
+
+use crate::chip8::devices::{Input, InputEvent};
+
+pub struct SdlInput {
+    event_pump: EventPump,
+}
+
+impl SdlInput {
+    pub fn new(sdl_context: &Sdl) -> Result<SdlInput, String> {
+        let event_pump = sdl_context.event_pump()?;
+
+        Ok(SdlInput { event_pump })
+    }
+
+    fn map_keycode(keycode: Keycode) -> Option<u8> {
+        match keycode {
+            Keycode::Num0 => Some(0x0),
+            Keycode::Num1 => Some(0x1),
+            Keycode::Num2 => Some(0x2),
+            Keycode::Num3 => Some(0x3),
+            Keycode::Num4 => Some(0x4),
+            Keycode::Num5 => Some(0x5),
+            Keycode::Num6 => Some(0x6),
+            Keycode::Num7 => Some(0x7),
+            Keycode::Num8 => Some(0x8),
+            Keycode::Num9 => Some(0x9),
+            Keycode::A => Some(0xA),
+            Keycode::B => Some(0xB),
+            Keycode::C => Some(0xC),
+            Keycode::D => Some(0xD),
+            Keycode::E => Some(0xE),
+            Keycode::F => Some(0xF),
+            _ => None,
+        }
+    }
+}
+
+impl Input for SdlInput {
+    fn poll_event(&mut self) -> Option<InputEvent> {
+        // Events that don't map to anything are skipped so callers can simply loop until None
+        while let Some(event) = self.event_pump.poll_event() {
+            let input_event = match event {
+                Event::Quit { .. } => Some(InputEvent::Quit),
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => match keycode {
+                    Keycode::PageUp => Some(InputEvent::SpeedUp),
+                    Keycode::PageDown => Some(InputEvent::SpeedDown),
+                    Keycode::F1 => Some(InputEvent::Break),
+                    Keycode::F5 => Some(InputEvent::SaveState),
+                    Keycode::F6 => Some(InputEvent::PreviousStateSlot),
+                    Keycode::F7 => Some(InputEvent::NextStateSlot),
+                    Keycode::F9 => Some(InputEvent::LoadState),
+                    Keycode::F10 => Some(InputEvent::ToggleScreenRecording),
+                    Keycode::F12 => Some(InputEvent::Screenshot),
+                    Keycode::Backspace => Some(InputEvent::RewindStart),
+                    _ => SdlInput::map_keycode(keycode).map(InputEvent::KeyDown),
+                },
+                Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => match keycode {
+                    Keycode::Backspace => Some(InputEvent::RewindStop),
+                    _ => SdlInput::map_keycode(keycode).map(InputEvent::KeyUp),
+                },
+                _ => None,
+            };
+
+            if input_event.is_some() {
+                return input_event;
+            }
+        }
+
+        None
+    }
+}
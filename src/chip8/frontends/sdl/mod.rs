@@ -0,0 +1,20 @@
+mod display;
+mod input;
+mod speaker;
+
+use crate::chip8::{Chip8Error, Palette};
+
+pub use display::SdlDisplay;
+pub use input::SdlInput;
+pub use speaker::SdlSpeaker;
+
+pub fn init(palette: Palette) -> Result<(SdlDisplay, SdlSpeaker, SdlInput), Chip8Error> {
+    let sdl_context = sdl2::init().map_err(Chip8Error::Device)?;
+
+    let display =
+        SdlDisplay::new(&sdl_context, palette).map_err(|e| Chip8Error::Device(e.to_string()))?;
+    let speaker = SdlSpeaker::new(&sdl_context).map_err(|e| Chip8Error::Device(e.to_string()))?;
+    let input = SdlInput::new(&sdl_context).map_err(Chip8Error::Device)?;
+
+    Ok((display, speaker, input))
+}
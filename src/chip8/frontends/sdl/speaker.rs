@@ -0,0 +1,98 @@
+use sdl2::{
+    audio::{AudioCallback, AudioDevice, AudioSpecDesired},
+    Sdl,
+};
+use std::error::Error;
+
+use crate::chip8::devices::Speaker;
+
+struct Tone {
+    pattern: Option<[u8; 16]>,
+    pattern_inc: f32,
+    pattern_position: f32,
+    phase_inc: f32,
+    phase: f32,
+    sample_rate: f32,
+    volume: f32,
+}
+
+impl Tone {
+    fn pattern_bit(pattern: &[u8; 16], position: f32) -> bool {
+        let bit = position as usize % 128;
+
+        pattern[bit / 8] & (0x80 >> (bit % 8)) != 0
+    }
+}
+
+impl AudioCallback for Tone {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for x in out.iter_mut() {
+            let high = match &self.pattern {
+                // Play the XO-CHIP pattern buffer one bit after the other
+                Some(pattern) => {
+                    let high = Tone::pattern_bit(pattern, self.pattern_position);
+                    self.pattern_position = (self.pattern_position + self.pattern_inc) % 128.0;
+                    high
+                }
+                // Generate a square wave
+                None => {
+                    let high = self.phase <= 0.5;
+                    self.phase = (self.phase + self.phase_inc) % 1.0;
+                    high
+                }
+            };
+
+            *x = if high { self.volume } else { -self.volume };
+        }
+    }
+}
+
+pub struct SdlSpeaker {
+    audio_device: AudioDevice<Tone>,
+}
+
+impl SdlSpeaker {
+    pub fn new(sdl_context: &Sdl) -> Result<SdlSpeaker, Box<dyn Error>> {
+        let audio_subsystem = sdl_context.audio()?;
+
+        let audio_spec = AudioSpecDesired {
+            freq: Some(44100),
+            channels: Some(1),
+            samples: None,
+        };
+
+        let audio_device = audio_subsystem.open_playback(None, &audio_spec, |spec| Tone {
+            pattern: None,
+            pattern_inc: 0.0,
+            pattern_position: 0.0,
+            phase_inc: 750.0 / spec.freq as f32,
+            phase: 0.0,
+            sample_rate: spec.freq as f32,
+            volume: 0.05,
+        })?;
+
+        Ok(SdlSpeaker { audio_device })
+    }
+}
+
+impl Speaker for SdlSpeaker {
+    fn start_beep(&mut self) {
+        self.audio_device.resume();
+    }
+
+    fn stop_beep(&mut self) {
+        self.audio_device.pause();
+    }
+
+    fn set_pattern(&mut self, pattern: &[u8; 16], pitch: u8) {
+        // 4000 bits per second at pitch 64, doubling every 48 steps
+        let bits_per_second = 4000.0 * 2f32.powf((pitch as f32 - 64.0) / 48.0);
+
+        let mut tone = self.audio_device.lock();
+
+        tone.pattern = Some(*pattern);
+        tone.pattern_inc = bits_per_second / tone.sample_rate;
+    }
+}
@@ -0,0 +1,211 @@
+use std::io::{self, Stdout, Write};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    execute, queue,
+    style::{Color, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+
+use crate::chip8::{devices::Display, Framebuffer, Palette};
+
+const UPPER_HALF_BLOCK: char = '▀';
+
+// Bit of each dot of a braille character, by row then column of its 2x4 cell
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+const BRAILLE_BLANK: u32 = 0x2800;
+
+// Draws the framebuffer with characters in the alternate screen of the terminal. Half blocks show
+// 2 pixels per character, the top one in the foreground colour and the bottom one in the
+// background colour, braille shows 8 in a single colour. Status messages are shown on the line
+// below the screen, anything printed the usual way would land over it.
+pub struct TtyDisplay {
+    braille: bool,
+    // Colours of the last frame drawn, a frame is only sent to the terminal when it changes
+    last_frame: Vec<u8>,
+    // Messages shown so far, printed again once the terminal is restored so they aren't lost
+    // with the alternate screen. True for errors.
+    messages: Vec<(String, bool)>,
+    palette: Palette,
+    // Terminal rows taken by the last frame drawn
+    rows: u16,
+    stdout: Stdout,
+}
+
+impl TtyDisplay {
+    pub fn new(braille: bool, palette: Palette) -> io::Result<TtyDisplay> {
+        let mut stdout = io::stdout();
+
+        terminal::enable_raw_mode()?;
+        execute!(stdout, EnterAlternateScreen, Hide, Clear(ClearType::All))?;
+
+        Ok(TtyDisplay {
+            braille,
+            last_frame: vec![],
+            messages: vec![],
+            palette,
+            rows: 0,
+            stdout,
+        })
+    }
+
+    fn draw_status(&self, out: &mut Vec<u8>) -> io::Result<()> {
+        queue!(out, MoveTo(0, self.rows), Clear(ClearType::CurrentLine))?;
+
+        if let Some((message, _)) = self.messages.last() {
+            write!(out, "{message}")?;
+        }
+
+        Ok(())
+    }
+
+    fn show_status(&mut self, message: &str, is_error: bool) {
+        self.messages.push((message.to_string(), is_error));
+
+        let mut out = vec![];
+
+        if self.draw_status(&mut out).is_ok() {
+            let _ = self
+                .stdout
+                .write_all(&out)
+                .and_then(|_| self.stdout.flush());
+        }
+    }
+
+    fn color(&self, index: u8) -> Color {
+        let (r, g, b) = self.palette.color(index);
+
+        Color::Rgb { r, g, b }
+    }
+
+    fn draw_half_blocks(&self, framebuffer: &Framebuffer, out: &mut Vec<u8>) -> io::Result<()> {
+        for row in 0..framebuffer.height() / 2 {
+            queue!(out, MoveTo(0, row as u16))?;
+
+            // Colours are only sent when they change from the previous character
+            let mut colors = None;
+
+            for x in 0..framebuffer.width() {
+                let top = framebuffer.color(x, row * 2);
+                let bottom = framebuffer.color(x, row * 2 + 1);
+
+                if colors != Some((top, bottom)) {
+                    queue!(
+                        out,
+                        SetForegroundColor(self.color(top)),
+                        SetBackgroundColor(self.color(bottom))
+                    )?;
+                    colors = Some((top, bottom));
+                }
+
+                write!(out, "{UPPER_HALF_BLOCK}")?;
+            }
+
+            queue!(out, ResetColor)?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_braille(&self, framebuffer: &Framebuffer, out: &mut Vec<u8>) -> io::Result<()> {
+        for row in 0..framebuffer.height() / 4 {
+            queue!(
+                out,
+                MoveTo(0, row as u16),
+                SetForegroundColor(self.color(1)),
+                SetBackgroundColor(self.color(0))
+            )?;
+
+            for column in 0..framebuffer.width() / 2 {
+                let mut character = BRAILLE_BLANK;
+
+                for (dy, dots) in BRAILLE_DOTS.iter().enumerate() {
+                    for (dx, dot) in dots.iter().enumerate() {
+                        if framebuffer.color(column * 2 + dx, row * 4 + dy) != 0 {
+                            character |= dot;
+                        }
+                    }
+                }
+
+                write!(out, "{}", char::from_u32(character).unwrap_or(' '))?;
+            }
+
+            queue!(out, ResetColor)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for TtyDisplay {
+    fn refresh(&mut self, framebuffer: &Framebuffer) -> Result<(), String> {
+        let (width, height) = (framebuffer.width(), framebuffer.height());
+
+        let mut frame = Vec::with_capacity(width * height);
+        for y in 0..height {
+            frame.extend((0..width).map(|x| framebuffer.color(x, y)));
+        }
+
+        if frame == self.last_frame {
+            return Ok(());
+        }
+
+        // Switching resolution leaves the rest of the larger screen behind
+        let mut out = vec![];
+
+        let resized = frame.len() != self.last_frame.len();
+
+        if resized {
+            self.rows = (height / if self.braille { 4 } else { 2 }) as u16;
+            queue!(out, Clear(ClearType::All)).map_err(|e| e.to_string())?;
+        }
+
+        if self.braille {
+            self.draw_braille(framebuffer, &mut out)
+        } else {
+            self.draw_half_blocks(framebuffer, &mut out)
+        }
+        .map_err(|e| e.to_string())?;
+
+        if resized {
+            self.draw_status(&mut out).map_err(|e| e.to_string())?;
+        }
+
+        self.stdout
+            .write_all(&out)
+            .and_then(|_| self.stdout.flush())
+            .map_err(|e| e.to_string())?;
+
+        self.last_frame = frame;
+
+        Ok(())
+    }
+
+    fn palette(&self) -> Palette {
+        self.palette
+    }
+
+    fn show_message(&mut self, message: &str) {
+        self.show_status(message, false);
+    }
+
+    fn show_error(&mut self, error: &str) {
+        self.show_status(error, true);
+    }
+}
+
+impl Drop for TtyDisplay {
+    // Gives the terminal back as it was, even when the emulator stops on an error
+    fn drop(&mut self) {
+        let _ = execute!(self.stdout, ResetColor, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+
+        for (message, is_error) in &self.messages {
+            if *is_error {
+                eprintln!("{message}");
+            } else {
+                println!("{message}");
+            }
+        }
+    }
+}
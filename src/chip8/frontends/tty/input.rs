@@ -0,0 +1,134 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    event::{
+        self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute, terminal,
+};
+
+use crate::chip8::devices::{Input, InputEvent};
+
+// Most terminals only report key presses, repeated while a key is held. A key counts as released
+// once it hasn't been repeated for this long, which covers the delay before the first repeat.
+// Terminals supporting the kitty keyboard protocol report releases as they happen.
+const KEY_HOLD: Duration = Duration::from_millis(500);
+
+pub struct TtyInput {
+    enhanced: bool,
+    // Keys down and when they count as released
+    held: Vec<(KeyCode, Instant)>,
+}
+
+impl TtyInput {
+    pub fn new() -> io::Result<TtyInput> {
+        // Terminals that don't answer the query are treated as not supporting it
+        let enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+
+        if enhanced {
+            execute!(
+                io::stdout(),
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::REPORT_EVENT_TYPES)
+            )?;
+        }
+
+        Ok(TtyInput {
+            enhanced,
+            held: vec![],
+        })
+    }
+
+    fn map_key(code: KeyCode) -> Option<u8> {
+        match code {
+            KeyCode::Char(c) => c.to_digit(16).map(|digit| digit as u8),
+            _ => None,
+        }
+    }
+
+    fn press(key: &KeyEvent) -> Option<InputEvent> {
+        match key.code {
+            KeyCode::Esc => Some(InputEvent::Quit),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(InputEvent::Quit)
+            }
+            KeyCode::PageUp => Some(InputEvent::SpeedUp),
+            KeyCode::PageDown => Some(InputEvent::SpeedDown),
+            KeyCode::F(5) => Some(InputEvent::SaveState),
+            KeyCode::F(6) => Some(InputEvent::PreviousStateSlot),
+            KeyCode::F(7) => Some(InputEvent::NextStateSlot),
+            KeyCode::F(9) => Some(InputEvent::LoadState),
+            KeyCode::F(10) => Some(InputEvent::ToggleScreenRecording),
+            KeyCode::F(12) => Some(InputEvent::Screenshot),
+            KeyCode::Backspace => Some(InputEvent::RewindStart),
+            code => TtyInput::map_key(code).map(InputEvent::KeyDown),
+        }
+    }
+
+    fn release(code: KeyCode) -> Option<InputEvent> {
+        match code {
+            KeyCode::Backspace => Some(InputEvent::RewindStop),
+            code => TtyInput::map_key(code).map(InputEvent::KeyUp),
+        }
+    }
+
+    fn handle_key(&mut self, key: &KeyEvent) -> Option<InputEvent> {
+        if key.kind == KeyEventKind::Release {
+            self.held.retain(|(code, _)| *code != key.code);
+
+            return TtyInput::release(key.code);
+        }
+
+        let release_at = Instant::now() + KEY_HOLD;
+
+        // A repeat only keeps the key down a little longer
+        if let Some((_, deadline)) = self.held.iter_mut().find(|(code, _)| *code == key.code) {
+            *deadline = release_at;
+            return None;
+        }
+
+        let event = TtyInput::press(key);
+
+        if TtyInput::release(key.code).is_some() {
+            self.held.push((key.code, release_at));
+        }
+
+        event
+    }
+}
+
+impl Drop for TtyInput {
+    fn drop(&mut self) {
+        if self.enhanced {
+            let _ = execute!(io::stdout(), PopKeyboardEnhancementFlags);
+        }
+    }
+}
+
+impl Input for TtyInput {
+    fn poll_event(&mut self) -> Option<InputEvent> {
+        let now = Instant::now();
+
+        if let Some(index) = self.held.iter().position(|(_, deadline)| *deadline <= now) {
+            let (code, _) = self.held.swap_remove(index);
+
+            return TtyInput::release(code);
+        }
+
+        // Events that don't map to anything are skipped so callers can simply loop until None
+        while event::poll(Duration::ZERO).unwrap_or(false) {
+            if let Ok(Event::Key(key)) = event::read() {
+                let input_event = self.handle_key(&key);
+
+                if input_event.is_some() {
+                    return input_event;
+                }
+            }
+        }
+
+        None
+    }
+}
@@ -0,0 +1,24 @@
+mod display;
+mod input;
+mod speaker;
+
+use crate::chip8::{Chip8Error, Palette};
+
+pub use display::TtyDisplay;
+pub use input::TtyInput;
+pub use speaker::TtySpeaker;
+
+/// Takes over the terminal until the display is dropped. `braille` draws 2x4 pixels per
+/// character instead of 1x2 with half blocks, `bell` rings the terminal bell on beeps.
+pub fn init(
+    braille: bool,
+    bell: bool,
+    palette: Palette,
+) -> Result<(TtyDisplay, TtySpeaker, TtyInput), Chip8Error> {
+    let display =
+        TtyDisplay::new(braille, palette).map_err(|e| Chip8Error::Device(e.to_string()))?;
+    let speaker = TtySpeaker::new(bell);
+    let input = TtyInput::new().map_err(|e| Chip8Error::Device(e.to_string()))?;
+
+    Ok((display, speaker, input))
+}
@@ -0,0 +1,34 @@
+use std::io::{self, Write};
+
+use crate::chip8::devices::Speaker;
+
+// The terminal bell rings once when a beep starts, it can't be held
+pub struct TtySpeaker {
+    beeping: bool,
+    bell: bool,
+}
+
+impl TtySpeaker {
+    pub fn new(bell: bool) -> TtySpeaker {
+        TtySpeaker {
+            beeping: false,
+            bell,
+        }
+    }
+}
+
+impl Speaker for TtySpeaker {
+    // Called on every frame of the beep
+    fn start_beep(&mut self) {
+        if self.bell && !self.beeping {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+        }
+
+        self.beeping = true;
+    }
+
+    fn stop_beep(&mut self) {
+        self.beeping = false;
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+
+/// The last snapshots taken with `Chip8::save_state`, oldest ones are dropped once `capacity` is
+/// reached. Only the latest snapshot is kept whole, every older one is stored as the run length
+/// encoded XOR with the snapshot that follows it, which is mostly zeros from one frame to the
+/// next.
+pub struct History {
+    capacity: usize,
+    deltas: VecDeque<Vec<u8>>,
+    latest: Option<Vec<u8>>,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> History {
+        History {
+            capacity,
+            deltas: VecDeque::new(),
+            latest: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.deltas.len() + self.latest.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.latest.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.deltas.clear();
+        self.latest = None;
+    }
+
+    pub fn push(&mut self, snapshot: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if let Some(latest) = self.latest.take() {
+            self.deltas
+                .push_back(History::encode_delta(&latest, &snapshot));
+        }
+
+        self.latest = Some(snapshot);
+
+        if self.len() > self.capacity {
+            self.deltas.pop_front();
+        }
+    }
+
+    pub fn latest(&self) -> Option<&[u8]> {
+        self.latest.as_deref()
+    }
+
+    /// Drops the latest snapshot, the one before it becomes the latest.
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let latest = self.latest.take()?;
+
+        self.latest = self
+            .deltas
+            .pop_back()
+            .map(|delta| History::apply_delta(&latest, &delta));
+
+        Some(latest)
+    }
+
+    // The length of the previous snapshot as a u32, then alternating runs of unchanged and
+    // changed bytes, each run starts with its length as a u16 and changed runs are followed by the
+    // XOR of their bytes. A shorter next snapshot counts as padded with zeros.
+    fn encode_delta(previous: &[u8], next: &[u8]) -> Vec<u8> {
+        let xor: Vec<u8> = previous
+            .iter()
+            .enumerate()
+            .map(|(index, previous)| previous ^ next.get(index).unwrap_or(&0))
+            .collect();
+
+        let mut delta = (previous.len() as u32).to_be_bytes().to_vec();
+        let mut position = 0;
+
+        while position < xor.len() {
+            let unchanged = History::run_length(&xor[position..], |byte| byte == 0);
+            delta.extend((unchanged as u16).to_be_bytes());
+            position += unchanged;
+
+            let changed = History::run_length(&xor[position..], |byte| byte != 0);
+            delta.extend((changed as u16).to_be_bytes());
+            delta.extend_from_slice(&xor[position..position + changed]);
+            position += changed;
+        }
+
+        delta
+    }
+
+    fn run_length(bytes: &[u8], predicate: impl Fn(u8) -> bool) -> usize {
+        bytes
+            .iter()
+            .take(u16::MAX as usize)
+            .take_while(|byte| predicate(**byte))
+            .count()
+    }
+
+    fn apply_delta(next: &[u8], delta: &[u8]) -> Vec<u8> {
+        let length = u32::from_be_bytes([delta[0], delta[1], delta[2], delta[3]]) as usize;
+
+        let mut previous = next.to_vec();
+        previous.resize(length, 0);
+
+        let mut position = 0;
+        let mut offset = 4;
+
+        while offset + 4 <= delta.len() {
+            position += u16::from_be_bytes([delta[offset], delta[offset + 1]]) as usize;
+            let changed = u16::from_be_bytes([delta[offset + 2], delta[offset + 3]]) as usize;
+            offset += 4;
+
+            for (byte, xor) in previous[position..position + changed]
+                .iter_mut()
+                .zip(&delta[offset..offset + changed])
+            {
+                *byte ^= xor;
+            }
+
+            position += changed;
+            offset += changed;
+        }
+
+        previous
+    }
+}
@@ -0,0 +1,52 @@
+use super::{
+    state::{StateReader, StateWriter},
+    Chip8Error,
+};
+
+pub struct Keypad {
+    keys: [bool; 16],
+}
+
+impl Keypad {
+    pub fn new() -> Keypad {
+        Keypad { keys: [false; 16] }
+    }
+
+    pub(super) fn save_state(&self, writer: &mut StateWriter) {
+        for pressed in self.keys {
+            writer.write_bool(pressed);
+        }
+    }
+
+    pub(super) fn load_state(&mut self, reader: &mut StateReader) -> Result<(), String> {
+        for pressed in &mut self.keys {
+            *pressed = reader.read_bool()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_key_pressed(&self, key: u8) -> Result<bool, Chip8Error> {
+        self.keys
+            .get(key as usize)
+            .copied()
+            .ok_or(Chip8Error::InvalidKey {
+                pc: None,
+                opcode: None,
+                stack: vec![],
+                key,
+            })
+    }
+
+    pub fn press_key(&mut self, key: u8) {
+        if let Some(pressed) = self.keys.get_mut(key as usize) {
+            *pressed = true;
+        }
+    }
+
+    pub fn release_key(&mut self, key: u8) {
+        if let Some(pressed) = self.keys.get_mut(key as usize) {
+            *pressed = false;
+        }
+    }
+}
@@ -0,0 +1,459 @@
+use super::{
+    common::fnv1a,
+    cpu::CPU,
+    rng::Rng,
+    state::{self, StateReader, StateWriter},
+    Backend, Chip8Error, FaultPolicy, Framebuffer, Quirks, Timing, Variant, WatchHit, Watchpoint,
+};
+
+pub const DEFAULT_INSTRUCTIONS_PER_FRAME: usize = 50; // This can vary a lot by programs, usually programs that are well designed should not care, but that's not always the case unfortunately
+
+/// Frames per second, the timers are decremented once per frame.
+pub const FRAME_RATE: u32 = 60;
+
+/// Instructions per second, `DEFAULT_INSTRUCTIONS_PER_FRAME` in every frame.
+pub const DEFAULT_CLOCK_RATE: u32 = DEFAULT_INSTRUCTIONS_PER_FRAME as u32 * FRAME_RATE;
+
+/// Highest clock rate, over 16 million instructions per frame.
+pub const MAX_CLOCK_RATE: u32 = 1_000_000_000;
+
+// The public face of the interpreter, everything a frontend or a tool needs goes through here so
+// the internals of the CPU can keep changing
+pub struct Chip8 {
+    cpu: CPU,
+    clock_rate: u32,
+    // Instructions owed to the next frames in 1/FRAME_RATE of an instruction, so a clock rate
+    // that isn't a multiple of the frame rate is still exact over a second
+    clock_remainder: u32,
+    rom_hash: u64,
+}
+
+impl Chip8 {
+    pub fn new() -> Chip8 {
+        Chip8::with_variant(Variant::default())
+    }
+
+    /// The variant decides which instructions exist and how much RAM there is.
+    pub fn with_variant(variant: Variant) -> Chip8 {
+        let mut cpu = CPU::new(variant);
+
+        // The font always fits in RAM
+        cpu.load_font_in_ram().unwrap();
+
+        Chip8 {
+            cpu,
+            clock_rate: DEFAULT_CLOCK_RATE,
+            clock_remainder: 0,
+            rom_hash: fnv1a(&[]),
+        }
+    }
+
+    /// Copies a program at 0x200, where execution starts.
+    pub fn load_rom(&mut self, rom: &[u8]) -> Result<(), Chip8Error> {
+        self.cpu.load_program_in_ram(rom)?;
+        self.rom_hash = fnv1a(rom);
+
+        Ok(())
+    }
+
+    /// Identifies the loaded ROM, save states and movies only apply to the ROM they were made with.
+    pub fn rom_hash(&self) -> u64 {
+        self.rom_hash
+    }
+
+    /// Snapshot of the whole machine, tied to the loaded ROM. Quirks, watchpoints and the speed
+    /// are settings and are not part of it.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut writer = StateWriter::new();
+
+        writer.write_bytes(state::MAGIC);
+        writer.write_u16(state::VERSION);
+        writer.write_u64(self.rom_hash);
+
+        let variant = self.cpu.variant.name();
+        writer.write_u8(variant.len() as u8);
+        writer.write_bytes(variant.as_bytes());
+
+        self.cpu.save_state(&mut writer);
+
+        writer.into_bytes()
+    }
+
+    /// Restores a snapshot taken by `save_state`, the machine is left untouched when it fails.
+    pub fn load_state(&mut self, bytes: &[u8]) -> Result<(), Chip8Error> {
+        self.read_state(bytes).map_err(Chip8Error::InvalidSaveState)
+    }
+
+    fn read_state(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let mut reader = StateReader::new(bytes);
+
+        if reader.read_bytes(state::MAGIC.len()).ok() != Some(state::MAGIC.as_slice()) {
+            return Err(String::from("This is not a save state."));
+        }
+
+        let version = reader.read_u16()?;
+
+        if version != state::VERSION {
+            return Err(format!(
+                "Save state version {version} is not supported, expected version {}.",
+                state::VERSION
+            ));
+        }
+
+        if reader.read_u64()? != self.rom_hash {
+            return Err(String::from(
+                "The save state was made with a different ROM.",
+            ));
+        }
+
+        let length = reader.read_u8()? as usize;
+        let variant = String::from_utf8_lossy(reader.read_bytes(length)?);
+
+        if variant != self.cpu.variant.name() {
+            return Err(format!(
+                "The save state was made with the {variant} variant, not {}.",
+                self.cpu.variant
+            ));
+        }
+
+        // Reading everything in a scratch machine first means a truncated file can't leave this
+        // one half loaded
+        let mut scratch_reader = reader.clone();
+        CPU::new(self.cpu.variant).load_state(&mut scratch_reader)?;
+
+        if !scratch_reader.is_at_end() {
+            return Err(String::from(
+                "The save state has unexpected trailing bytes.",
+            ));
+        }
+
+        self.cpu.load_state(&mut reader)
+    }
+
+    /// Fetches, decodes and executes a single instruction. Timers are left untouched.
+    pub fn step(&mut self) -> Result<(), Chip8Error> {
+        self.cpu.step()
+    }
+
+    /// Executes the instructions of 1/60th of a second at the clock rate then decrements the
+    /// timers once.
+    pub fn run_frame(&mut self) -> Result<(), Chip8Error> {
+        let instructions = self.frame_instructions();
+
+        self.cpu.tick(instructions)
+    }
+
+    /// Same as `run_frame` but asks `should_break` before every instruction. When it returns true
+    /// the frame stops right there, without decrementing the timers, and true is returned.
+    pub fn run_frame_until<F>(&mut self, mut should_break: F) -> Result<bool, Chip8Error>
+    where
+        F: FnMut(&Chip8) -> bool,
+    {
+        self.cpu.start_frame();
+
+        let mut instructions = self.frame_instructions();
+
+        match self.cpu.backend {
+            Backend::Interpreter => {
+                for _ in 0..instructions {
+                    if should_break(self) {
+                        return Ok(true);
+                    }
+
+                    self.cpu.step()?;
+
+                    if self.cpu.is_frame_over() {
+                        break;
+                    }
+                }
+            }
+            // Blocks end early on a break, the instructions left in them are translated again if
+            // the frame resumes from there
+            Backend::Blocks => {
+                'frame: while instructions > 0 && !self.cpu.halted {
+                    let (start, block) = self.cpu.current_block()?;
+
+                    for compiled in block.iter().take(instructions) {
+                        if should_break(self) {
+                            return Ok(true);
+                        }
+
+                        let fresh = self.cpu.run_block_instruction(&block, start, compiled)?;
+                        instructions -= 1;
+
+                        if self.cpu.is_frame_over() {
+                            break 'frame;
+                        }
+
+                        if !fresh {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.cpu.tick_timers();
+
+        Ok(false)
+    }
+
+    /// True once the program exited with 00FD or stopped on a fault, nothing runs afterwards. A
+    /// fault leaves the PC on the faulting instruction.
+    pub fn is_halted(&self) -> bool {
+        self.cpu.halted
+    }
+
+    /// Instructions executed since the machine was created, part of save states.
+    pub fn instruction_count(&self) -> u64 {
+        self.cpu.instruction_count
+    }
+
+    /// Makes CXKK return the same sequence on every run, the generator is otherwise seeded
+    /// randomly. Its state is part of save states.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.cpu.rng = Rng::from_seed(seed);
+    }
+
+    /// Instructions executed per second, `DEFAULT_CLOCK_RATE` unless changed.
+    pub fn clock_rate(&self) -> u32 {
+        self.clock_rate
+    }
+
+    /// Higher clock rates are clamped to `MAX_CLOCK_RATE`.
+    pub fn set_clock_rate(&mut self, clock_rate: u32) {
+        self.clock_rate = clock_rate.min(MAX_CLOCK_RATE);
+    }
+
+    /// The clock rate in instructions per frame, rounded down.
+    pub fn instructions_per_frame(&self) -> usize {
+        (self.clock_rate / FRAME_RATE) as usize
+    }
+
+    /// Fails when that's more than `MAX_CLOCK_RATE`, the clock rate is left alone then.
+    pub fn set_instructions_per_frame(
+        &mut self,
+        instructions_per_frame: usize,
+    ) -> Result<(), String> {
+        let clock_rate = u32::try_from(instructions_per_frame)
+            .ok()
+            .and_then(|instructions| instructions.checked_mul(FRAME_RATE))
+            .filter(|clock_rate| *clock_rate <= MAX_CLOCK_RATE)
+            .ok_or_else(|| {
+                format!(
+                    "{instructions_per_frame} instructions per frame is over the maximum clock \
+                     rate of {MAX_CLOCK_RATE} instructions per second."
+                )
+            })?;
+
+        self.set_clock_rate(clock_rate);
+
+        Ok(())
+    }
+
+    /// Decoded instructions are cached by address unless disabled, which only makes a difference
+    /// to the speed.
+    pub fn set_instruction_cache(&mut self, enabled: bool) {
+        self.cpu.cache_enabled = enabled;
+    }
+
+    pub fn backend(&self) -> Backend {
+        self.cpu.backend
+    }
+
+    /// `Backend::Blocks` runs frames from translated blocks of code, with the same results as the
+    /// interpreter.
+    pub fn set_backend(&mut self, backend: Backend) {
+        self.cpu.backend = backend;
+    }
+
+    /// Blocks of code translated by `Backend::Blocks` since the machine was created, always 0 with
+    /// the interpreter.
+    pub fn translated_blocks(&self) -> u64 {
+        self.cpu.blocks.translated()
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.cpu.timing
+    }
+
+    /// `Timing::CosmacVip` budgets frames in machine cycles of the VIP instead of the clock rate.
+    pub fn set_timing(&mut self, timing: Timing) {
+        self.cpu.timing = timing;
+        self.cpu.cycles = 0;
+    }
+
+    // 1000Hz runs 16 or 17 instructions per frame, 1000 every 60 frames
+    fn frame_instructions(&mut self) -> usize {
+        // The CPU stops the frame itself once its cycles are spent
+        if self.cpu.timing == Timing::CosmacVip {
+            return usize::MAX;
+        }
+
+        let owed = self.clock_remainder as u64 + self.clock_rate as u64;
+        self.clock_remainder = (owed % FRAME_RATE as u64) as u32;
+
+        (owed / FRAME_RATE as u64) as usize
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.cpu.variant
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.cpu.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.cpu.quirks = quirks;
+    }
+
+    pub fn fault_policy(&self) -> FaultPolicy {
+        self.cpu.fault_policy
+    }
+
+    /// How stack overflows and underflows are handled, `FaultPolicy::Halt` by default.
+    pub fn set_fault_policy(&mut self, fault_policy: FaultPolicy) {
+        self.cpu.fault_policy = fault_policy;
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.cpu.strict
+    }
+
+    /// In strict mode unknown opcodes and 0NNN machine code calls fault instead of being skipped.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.cpu.strict = strict;
+    }
+
+    pub fn registers(&self) -> &[u8; 16] {
+        &self.cpu.v
+    }
+
+    /// Sets V0 to VF, `index` is the number of the register.
+    ///
+    /// # Panics
+    ///
+    /// Panics when `index` is 16 or more.
+    pub fn set_register(&mut self, index: usize, value: u8) {
+        self.cpu.v[index] = value;
+    }
+
+    pub fn i(&self) -> u16 {
+        self.cpu.i
+    }
+
+    pub fn set_i(&mut self, value: u16) {
+        self.cpu.i = value;
+    }
+
+    pub fn pc(&self) -> u16 {
+        self.cpu.pc
+    }
+
+    pub fn set_pc(&mut self, value: u16) {
+        self.cpu.pc = value;
+    }
+
+    pub fn sp(&self) -> u8 {
+        self.cpu.sp
+    }
+
+    /// The 2 bytes at `address` as an opcode, if they are in RAM.
+    pub fn opcode_at(&self, address: u16) -> Option<u16> {
+        self.read_ram(address as usize, 2)
+            .ok()
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn stack(&self) -> &[u16; 16] {
+        &self.cpu.stack
+    }
+
+    pub fn delay_timer(&self) -> u8 {
+        self.cpu.delay_timer
+    }
+
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.cpu.delay_timer = value;
+    }
+
+    pub fn sound_timer(&self) -> u8 {
+        self.cpu.sound_timer
+    }
+
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.cpu.sound_timer = value;
+    }
+
+    /// The XO-CHIP audio pattern buffer and its pitch, `None` until the program loads a pattern
+    /// with F002, in which case the classic beep should be played.
+    pub fn audio_pattern(&self) -> Option<(&[u8; 16], u8)> {
+        self.cpu
+            .audio_pattern
+            .as_ref()
+            .map(|pattern| (pattern, self.cpu.pitch))
+    }
+
+    pub fn ram_size(&self) -> usize {
+        self.cpu.ram.size()
+    }
+
+    /// Reading from here never triggers watchpoints, only the program's own accesses do.
+    pub fn read_ram(&self, address: usize, bytes_to_read: usize) -> Result<&[u8], Chip8Error> {
+        self.cpu.ram.peek(address, bytes_to_read)
+    }
+
+    pub fn write_ram(&mut self, address: usize, data: &[u8]) -> Result<usize, Chip8Error> {
+        let bytes_written = self.cpu.ram.write(address, data)?;
+
+        // Same as reads, watchpoints are for the program's own accesses
+        self.cpu.ram.take_watch_hit();
+
+        Ok(bytes_written)
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        self.cpu.add_watchpoint(watchpoint);
+    }
+
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        self.cpu.remove_watchpoint(watchpoint)
+    }
+
+    pub fn watchpoints(&self) -> Vec<Watchpoint> {
+        self.cpu.watchpoints()
+    }
+
+    /// The watchpoint triggered by the last instruction executed, if any.
+    pub fn watch_hit(&self) -> Option<WatchHit> {
+        self.cpu.watch_hit
+    }
+
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.cpu.framebuffer
+    }
+
+    pub fn framebuffer_mut(&mut self) -> &mut Framebuffer {
+        &mut self.cpu.framebuffer
+    }
+
+    pub fn is_key_pressed(&self, key: u8) -> bool {
+        self.cpu.keypad.is_key_pressed(key).unwrap_or(false)
+    }
+
+    /// Keys are numbered 0x0 to 0xF like on the original hex keypad, anything else is ignored.
+    pub fn set_key(&mut self, key: u8, pressed: bool) {
+        if pressed {
+            self.cpu.keypad.press_key(key);
+        } else {
+            self.cpu.keypad.release_key(key);
+        }
+    }
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Chip8::new()
+    }
+}
@@ -1,9 +1,54 @@
+mod asm;
+mod blocks;
+mod cache;
 mod common;
 mod cpu;
+mod debugger;
+mod disasm;
 mod emulator;
+mod error;
+mod fault;
+mod framebuffer;
+mod history;
+mod keypad;
+mod machine;
+mod movie;
+mod palette;
+mod quirks;
 mod ram;
+mod recorder;
+mod rng;
+mod scheduler;
+mod screen;
+mod screenshot;
+mod state;
+mod timing;
+mod variant;
+mod watchpoints;
 
 pub mod devices;
+pub mod frontends;
 
-pub use common::{Position, FONT_DATA};
+pub use asm::{assemble, assemble_file};
+pub use blocks::Backend;
+pub use common::{Position, BIG_FONT_DATA, FONT_DATA};
+pub use debugger::Debugger;
+pub use disasm::{disassemble, disassemble_instruction, DisassembledLine, Disassembly};
 pub use emulator::Emulator;
+pub use error::Chip8Error;
+pub use fault::FaultPolicy;
+pub use framebuffer::{Framebuffer, HEIGHT, HIRES_HEIGHT, HIRES_WIDTH, WIDTH};
+pub use history::History;
+pub use keypad::Keypad;
+pub use machine::{
+    Chip8, DEFAULT_CLOCK_RATE, DEFAULT_INSTRUCTIONS_PER_FRAME, FRAME_RATE, MAX_CLOCK_RATE,
+};
+pub use movie::{Movie, MovieEvent, MovieInput};
+pub use palette::{Palette, Rgb};
+pub use quirks::{Quirks, QuirksPreset};
+pub use recorder::{ScreenRecorder, MAX_SCREENSHOT_SCALE};
+pub use screen::{screen_diff, screen_hash, screen_text};
+pub use screenshot::{save_screenshot, screenshot_png};
+pub use timing::{Timing, VIP_INTERPRETER_CYCLES};
+pub use variant::Variant;
+pub use watchpoints::{WatchHit, Watchpoint};
@@ -0,0 +1,242 @@
+use std::{fmt, str::FromStr};
+
+use super::{Chip8, FaultPolicy, Quirks, Timing, Variant};
+
+const HEADER: &str = "chip-8-rs movie 1";
+
+/// Everything needed to replay a play session frame for frame: the settings the program ran with
+/// and every input along with the frame it happened before.
+///
+/// Movies are text files:
+///
+/// ```text
+/// chip-8-rs movie 1
+/// rom 9c2a1fd5e0b3a2c4
+/// variant schip
+/// quirks clip_sprites jump_uses_vx
+/// seed 1234
+/// clock 3000
+/// timing instructions
+/// faults halt
+/// strict false
+/// 12 down 5
+/// 20 up 5
+/// 31 clock 3060
+/// end 600
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_hash: u64,
+    pub variant: Variant,
+    pub quirks: Quirks,
+    pub seed: u64,
+    /// Clock rate in Hz at the start.
+    pub clock_rate: u32,
+    pub timing: Timing,
+    pub fault_policy: FaultPolicy,
+    /// Whether unknown opcodes and machine code calls fault.
+    pub strict: bool,
+    pub events: Vec<MovieEvent>,
+    /// Number of frames the recording lasted.
+    pub length: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MovieEvent {
+    pub frame: u64,
+    pub input: MovieInput,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MovieInput {
+    KeyDown(u8),
+    KeyUp(u8),
+    ClockRate(u32),
+}
+
+impl Movie {
+    /// An empty movie with the current settings of the machine, which must run with `seed`.
+    pub fn new(chip8: &Chip8, seed: u64) -> Movie {
+        Movie {
+            rom_hash: chip8.rom_hash(),
+            variant: chip8.variant(),
+            quirks: chip8.quirks(),
+            seed,
+            clock_rate: chip8.clock_rate(),
+            timing: chip8.timing(),
+            fault_policy: chip8.fault_policy(),
+            strict: chip8.is_strict(),
+            events: vec![],
+            length: 0,
+        }
+    }
+
+    pub fn record(&mut self, frame: u64, input: MovieInput) {
+        self.events.push(MovieEvent { frame, input });
+    }
+
+    // Quirks are written as the names of the ones enabled
+    fn quirk_flags(quirks: &mut Quirks) -> [(&'static str, &mut bool); 6] {
+        [
+            ("clip_sprites", &mut quirks.clip_sprites),
+            ("display_wait", &mut quirks.display_wait),
+            ("jump_uses_vx", &mut quirks.jump_uses_vx),
+            (
+                "load_store_increments_i",
+                &mut quirks.load_store_increments_i,
+            ),
+            ("shift_uses_vy", &mut quirks.shift_uses_vy),
+            ("vf_reset", &mut quirks.vf_reset),
+        ]
+    }
+
+    fn parse_quirks(names: &[&str]) -> Result<Quirks, String> {
+        let mut quirks = Quirks {
+            clip_sprites: false,
+            display_wait: false,
+            jump_uses_vx: false,
+            load_store_increments_i: false,
+            shift_uses_vy: false,
+            vf_reset: false,
+        };
+
+        for name in names {
+            let flag = Movie::quirk_flags(&mut quirks)
+                .into_iter()
+                .find(|(flag_name, _)| flag_name == name)
+                .ok_or_else(|| format!("unknown quirk '{name}'"))?;
+
+            *flag.1 = true;
+        }
+
+        Ok(quirks)
+    }
+
+    fn parse_number<T: FromStr>(word: Option<&str>) -> Result<T, String> {
+        let word = word.ok_or("missing number")?;
+
+        word.parse().map_err(|_| format!("invalid number '{word}'"))
+    }
+
+    fn parse_key(word: Option<&str>) -> Result<u8, String> {
+        let word = word.ok_or("missing key")?;
+
+        u8::from_str_radix(word, 16)
+            .ok()
+            .filter(|key| *key < 16)
+            .ok_or_else(|| format!("invalid key '{word}'"))
+    }
+}
+
+impl fmt::Display for Movie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        writeln!(f, "rom {:016x}", self.rom_hash)?;
+        writeln!(f, "variant {}", self.variant)?;
+
+        let mut quirks = self.quirks;
+        let mut words = vec!["quirks"];
+
+        for (name, enabled) in Movie::quirk_flags(&mut quirks) {
+            if *enabled {
+                words.push(name);
+            }
+        }
+
+        writeln!(f, "{}", words.join(" "))?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "clock {}", self.clock_rate)?;
+        writeln!(f, "timing {}", self.timing)?;
+        writeln!(f, "faults {}", self.fault_policy)?;
+        writeln!(f, "strict {}", self.strict)?;
+
+        for event in &self.events {
+            match event.input {
+                MovieInput::KeyDown(key) => writeln!(f, "{} down {:X}", event.frame, key)?,
+                MovieInput::KeyUp(key) => writeln!(f, "{} up {:X}", event.frame, key)?,
+                MovieInput::ClockRate(clock_rate) => {
+                    writeln!(f, "{} clock {}", event.frame, clock_rate)?
+                }
+            }
+        }
+
+        writeln!(f, "end {}", self.length)
+    }
+}
+
+impl FromStr for Movie {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().enumerate();
+
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err(format!("not a movie, the first line should be '{HEADER}'"));
+        }
+
+        let mut rom_hash = None;
+        let mut variant = None;
+        let mut quirks = None;
+        let mut seed = None;
+        let mut clock_rate = None;
+        let mut timing = None;
+        let mut fault_policy = None;
+        let mut strict = None;
+        let mut events = vec![];
+        let mut length = None;
+
+        for (index, line) in lines {
+            let words: Vec<&str> = line.split_whitespace().collect();
+
+            let result = match words.as_slice() {
+                [] => Ok(()),
+                ["rom", hash] => u64::from_str_radix(hash, 16)
+                    .map(|hash| rom_hash = Some(hash))
+                    .map_err(|_| format!("invalid ROM hash '{hash}'")),
+                ["variant", name] => name.parse().map(|name| variant = Some(name)),
+                ["quirks", names @ ..] => Movie::parse_quirks(names).map(|q| quirks = Some(q)),
+                ["seed", value] => Movie::parse_number(Some(value)).map(|value| seed = Some(value)),
+                ["clock", value] => {
+                    Movie::parse_number(Some(value)).map(|value| clock_rate = Some(value))
+                }
+                ["timing", name] => name.parse().map(|name| timing = Some(name)),
+                ["faults", name] => name.parse().map(|name| fault_policy = Some(name)),
+                ["strict", value] => value
+                    .parse()
+                    .map(|value| strict = Some(value))
+                    .map_err(|_| format!("invalid strict mode '{value}'")),
+                ["end", value] => {
+                    Movie::parse_number(Some(value)).map(|value| length = Some(value))
+                }
+                [frame, kind, argument] => Movie::parse_number(Some(frame)).and_then(|frame| {
+                    let input = match *kind {
+                        "down" => MovieInput::KeyDown(Movie::parse_key(Some(argument))?),
+                        "up" => MovieInput::KeyUp(Movie::parse_key(Some(argument))?),
+                        "clock" => MovieInput::ClockRate(Movie::parse_number(Some(argument))?),
+                        _ => return Err(format!("unknown event '{kind}'")),
+                    };
+
+                    events.push(MovieEvent { frame, input });
+
+                    Ok(())
+                }),
+                _ => Err(format!("invalid line '{}'", line.trim())),
+            };
+
+            result.map_err(|e| format!("line {}: {e}", index + 1))?;
+        }
+
+        Ok(Movie {
+            rom_hash: rom_hash.ok_or("missing 'rom' line")?,
+            variant: variant.ok_or("missing 'variant' line")?,
+            quirks: quirks.ok_or("missing 'quirks' line")?,
+            seed: seed.ok_or("missing 'seed' line")?,
+            clock_rate: clock_rate.ok_or("missing 'clock' line")?,
+            timing: timing.ok_or("missing 'timing' line")?,
+            fault_policy: fault_policy.ok_or("missing 'faults' line")?,
+            strict: strict.ok_or("missing 'strict' line")?,
+            events,
+            length: length.ok_or("missing 'end' line")?,
+        })
+    }
+}
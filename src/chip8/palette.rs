@@ -0,0 +1,56 @@
+use std::str::FromStr;
+
+pub type Rgb = (u8, u8, u8);
+
+// Colours for the 4 values a pixel can take with XO-CHIP's 2 bitplanes, programs using a single
+// plane only ever show the first 2
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub colors: [Rgb; 4],
+}
+
+impl Palette {
+    pub fn color(&self, index: u8) -> Rgb {
+        self.colors[(index & 0x3) as usize]
+    }
+}
+
+// Colours in hex separated by commas, e.g. `0F380F,9BBC0F` or all 4 of them. Those left out keep
+// their default.
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let colors: Vec<&str> = s.split(',').map(str::trim).collect();
+
+        if colors.len() > 4 {
+            return Err(format!(
+                "A palette has at most 4 colours, found {}.",
+                colors.len()
+            ));
+        }
+
+        let mut palette = Palette::default();
+
+        for (color, text) in palette.colors.iter_mut().zip(colors) {
+            let hex = text.strip_prefix('#').unwrap_or(text);
+
+            if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("Invalid colour '{text}', expected RRGGBB in hex."));
+            }
+
+            let [_, r, g, b] = u32::from_str_radix(hex, 16).unwrap_or(0).to_be_bytes();
+            *color = (r, g, b);
+        }
+
+        Ok(palette)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette {
+            colors: [(0, 0, 0), (255, 255, 255), (255, 102, 0), (102, 34, 0)],
+        }
+    }
+}
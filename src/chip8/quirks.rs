@@ -0,0 +1,107 @@
+use std::{fmt, str::FromStr};
+
+// Behaviours that changed between interpreters over the years, programs usually only work
+// properly with the ones of the interpreter they were written for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quirks {
+    /// Sprites going past the edges of the screen are cut instead of wrapping around.
+    pub clip_sprites: bool,
+    /// Drawing a sprite waits for the next frame, so at most one sprite is drawn per frame.
+    pub display_wait: bool,
+    /// BNNN jumps to NNN + VX (BXNN) instead of NNN + V0.
+    pub jump_uses_vx: bool,
+    /// FX55 and FX65 leave I pointing after the last register stored or loaded.
+    pub load_store_increments_i: bool,
+    /// 8XY6 and 8XYE shift VY into VX instead of shifting VX in place.
+    pub shift_uses_vy: bool,
+    /// 8XY1, 8XY2 and 8XY3 set VF to 0.
+    pub vf_reset: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QuirksPreset {
+    CosmacVip,
+    Chip48,
+    SuperChip,
+    Modern,
+}
+
+impl Quirks {
+    pub fn from_preset(preset: QuirksPreset) -> Quirks {
+        match preset {
+            QuirksPreset::CosmacVip => Quirks {
+                clip_sprites: true,
+                display_wait: true,
+                jump_uses_vx: false,
+                load_store_increments_i: true,
+                shift_uses_vy: true,
+                vf_reset: true,
+            },
+            QuirksPreset::Chip48 | QuirksPreset::SuperChip => Quirks {
+                clip_sprites: true,
+                display_wait: false,
+                jump_uses_vx: true,
+                load_store_increments_i: false,
+                shift_uses_vy: false,
+                vf_reset: false,
+            },
+            QuirksPreset::Modern => Quirks {
+                clip_sprites: false,
+                display_wait: false,
+                jump_uses_vx: false,
+                load_store_increments_i: false,
+                shift_uses_vy: false,
+                vf_reset: false,
+            },
+        }
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Quirks::from_preset(QuirksPreset::Modern)
+    }
+}
+
+impl QuirksPreset {
+    pub const ALL: [QuirksPreset; 4] = [
+        QuirksPreset::CosmacVip,
+        QuirksPreset::Chip48,
+        QuirksPreset::SuperChip,
+        QuirksPreset::Modern,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuirksPreset::CosmacVip => "vip",
+            QuirksPreset::Chip48 => "chip48",
+            QuirksPreset::SuperChip => "schip",
+            QuirksPreset::Modern => "modern",
+        }
+    }
+}
+
+impl fmt::Display for QuirksPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for QuirksPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        QuirksPreset::ALL
+            .into_iter()
+            .find(|preset| preset.name() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = QuirksPreset::ALL.iter().map(|p| p.name()).collect();
+
+                format!(
+                    "Unknown quirks preset '{}', expected one of: {}.",
+                    s.trim(),
+                    names.join(", ")
+                )
+            })
+    }
+}
@@ -1,39 +1,177 @@
-const RAM_SIZE_IN_BYTES: usize = 4096;
+use std::{cell::Cell, collections::BTreeSet, ops::Range};
 
+use super::{
+    state::{StateReader, StateWriter},
+    Chip8Error, Watchpoint,
+};
+
+#[allow(clippy::upper_case_acronyms)]
 pub struct RAM {
-    data: [u8; RAM_SIZE_IN_BYTES],
+    data: Vec<u8>,
+    read_watchpoints: BTreeSet<u16>,
+    // Reads only borrow the RAM, the hit is stored in a cell so they can still report it
+    watch_hit: Cell<Option<Watchpoint>>,
+    write_watchpoints: BTreeSet<u16>,
+    // Addresses written since the CPU last looked, it decodes the instructions there again
+    written: Option<Range<usize>>,
 }
 
 impl RAM {
-    pub fn new() -> RAM {
-        RAM { data: [0; 4096] }
+    pub fn new(size_in_bytes: usize) -> RAM {
+        RAM {
+            data: vec![0; size_in_bytes],
+            read_watchpoints: BTreeSet::new(),
+            watch_hit: Cell::new(None),
+            write_watchpoints: BTreeSet::new(),
+            written: None,
+        }
     }
 
-    pub fn write(&mut self, address: usize, data: &[u8]) -> Result<usize, String> {
-        let number_of_bytes = data.len();
+    pub fn size(&self) -> usize {
+        self.data.len()
+    }
+
+    // Watchpoints are a debugging setting and stay out of save states
+    pub fn save_state(&self, writer: &mut StateWriter) {
+        writer.write_u32(self.data.len() as u32);
+        writer.write_bytes(&self.data);
+    }
+
+    pub fn load_state(&mut self, reader: &mut StateReader) -> Result<(), String> {
+        let size = reader.read_u32()? as usize;
 
-        if address + number_of_bytes >= RAM_SIZE_IN_BYTES {
+        if size != self.data.len() {
             return Err(format!(
-                "Trying to write {} bytes at address {:#04X?} would exceed bounds({} bytes) of the RAM.",
-                number_of_bytes, address, RAM_SIZE_IN_BYTES
+                "The save state has {size} bytes of RAM instead of {}.",
+                self.data.len()
             ));
         }
 
-        for (byte_index, byte) in data.iter().enumerate() {
-            self.data[address + byte_index] = byte.clone();
+        self.data.copy_from_slice(reader.read_bytes(size)?);
+        self.mark_written(0..size);
+
+        Ok(())
+    }
+
+    pub fn write(&mut self, address: usize, data: &[u8]) -> Result<usize, Chip8Error> {
+        let number_of_bytes = data.len();
+
+        if address + number_of_bytes > self.data.len() {
+            return Err(self.out_of_bounds(address, number_of_bytes));
         }
 
+        if let Some(watched) = RAM::first_watched(&self.write_watchpoints, address, number_of_bytes)
+        {
+            self.watch_hit.set(Some(Watchpoint::MemoryWrite(watched)));
+        }
+
+        self.data[address..address + number_of_bytes].copy_from_slice(data);
+        self.mark_written(address..address + number_of_bytes);
+
         Ok(number_of_bytes)
     }
 
-    pub fn read(&self, address: usize, bytes_to_read: usize) -> Result<&[u8], String> {
-        if address + bytes_to_read >= RAM_SIZE_IN_BYTES {
-            return Err(format!(
-                "Trying to read {} bytes at address {:#04X?} would exceed bounds({} bytes) of the RAM.",
-                bytes_to_read, address, RAM_SIZE_IN_BYTES
-            ));
+    pub fn read(&self, address: usize, bytes_to_read: usize) -> Result<&[u8], Chip8Error> {
+        let bytes = self.peek(address, bytes_to_read)?;
+
+        if let Some(watched) = RAM::first_watched(&self.read_watchpoints, address, bytes_to_read) {
+            self.watch_hit.set(Some(Watchpoint::MemoryRead(watched)));
+        }
+
+        Ok(bytes)
+    }
+
+    // Same as read without triggering watchpoints, for instruction fetches and the debugger
+    pub fn peek(&self, address: usize, bytes_to_read: usize) -> Result<&[u8], Chip8Error> {
+        if address + bytes_to_read > self.data.len() {
+            return Err(self.out_of_bounds(address, bytes_to_read));
         }
 
         Ok(&self.data[address..address + bytes_to_read])
     }
+
+    // The CPU adds the instruction that made the access
+    fn out_of_bounds(&self, address: usize, length: usize) -> Chip8Error {
+        Chip8Error::MemoryOutOfBounds {
+            pc: None,
+            opcode: None,
+            stack: vec![],
+            address,
+            length,
+            ram_size: self.data.len(),
+        }
+    }
+
+    pub fn add_watchpoint(&mut self, watchpoint: Watchpoint) {
+        match watchpoint {
+            Watchpoint::MemoryRead(address) => self.read_watchpoints.insert(address),
+            Watchpoint::MemoryWrite(address) => self.write_watchpoints.insert(address),
+            _ => false,
+        };
+    }
+
+    pub fn remove_watchpoint(&mut self, watchpoint: &Watchpoint) -> bool {
+        match watchpoint {
+            Watchpoint::MemoryRead(address) => self.read_watchpoints.remove(address),
+            Watchpoint::MemoryWrite(address) => self.write_watchpoints.remove(address),
+            _ => false,
+        }
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = Watchpoint> + '_ {
+        let reads = self
+            .read_watchpoints
+            .iter()
+            .copied()
+            .map(Watchpoint::MemoryRead);
+        let writes = self
+            .write_watchpoints
+            .iter()
+            .copied()
+            .map(Watchpoint::MemoryWrite);
+
+        reads.chain(writes)
+    }
+
+    fn mark_written(&mut self, range: Range<usize>) {
+        self.written = Some(match self.written.take() {
+            Some(written) => written.start.min(range.start)..written.end.max(range.end),
+            None => range,
+        });
+    }
+
+    /// Smallest range covering every write since the last call.
+    pub fn take_written(&mut self) -> Option<Range<usize>> {
+        self.written.take()
+    }
+
+    /// Same as take_written, leaving the range for the next call.
+    pub fn written(&self) -> Option<Range<usize>> {
+        self.written.clone()
+    }
+
+    pub fn has_watchpoints(&self) -> bool {
+        !self.read_watchpoints.is_empty() || !self.write_watchpoints.is_empty()
+    }
+
+    pub fn has_watch_hit(&self) -> bool {
+        self.watch_hit.get().is_some()
+    }
+
+    pub fn take_watch_hit(&self) -> Option<Watchpoint> {
+        self.watch_hit.take()
+    }
+
+    fn first_watched(watchpoints: &BTreeSet<u16>, address: usize, length: usize) -> Option<u16> {
+        if watchpoints.is_empty() || length == 0 {
+            return None;
+        }
+
+        let end = (address + length - 1).min(u16::MAX as usize);
+
+        watchpoints
+            .range(address.min(u16::MAX as usize) as u16..=end as u16)
+            .next()
+            .copied()
+    }
 }
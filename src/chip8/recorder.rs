@@ -0,0 +1,234 @@
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use super::{screenshot, Chip8Error, Framebuffer, Palette, FRAME_RATE, HIRES_HEIGHT, HIRES_WIDTH};
+
+// GIF delays are in hundredths of a second, and browsers play frames shorter than 2 hundredths
+// much slower than asked. Frames are timed on the 60Hz timeline and one shown for less than that
+// is replaced by the next one.
+const GIF_MIN_DELAY: u64 = 2;
+
+/// Largest scale of screenshots and recordings, GIF frames are at most 65535 pixels wide.
+pub const MAX_SCREENSHOT_SCALE: usize = u16::MAX as usize / HIRES_WIDTH;
+
+enum Output {
+    Gif {
+        encoder: gif::Encoder<BufWriter<File>>,
+        size: (u16, u16),
+        // Frame waiting to know how long it's shown, with the frame number it started at
+        pending: Option<(Vec<u8>, u64)>,
+    },
+    Frames {
+        directory: PathBuf,
+    },
+}
+
+/// Records the screen frame by frame at 60 frames per second, as an animated GIF when the path
+/// ends with `.gif` and as numbered PNG images in a directory otherwise, `000001.png` and on.
+/// Frames are the size of the high resolution screen times the scale, low resolution frames are
+/// drawn with pixels twice as large so a program can switch between both.
+pub struct ScreenRecorder {
+    frames: u64,
+    output: Option<Output>,
+    palette: Palette,
+    path: PathBuf,
+    size: (usize, usize),
+}
+
+impl ScreenRecorder {
+    pub fn new(path: impl AsRef<Path>, palette: Palette, scale: usize) -> ScreenRecorder {
+        let scale = scale.max(1);
+
+        ScreenRecorder {
+            frames: 0,
+            output: None,
+            palette,
+            path: path.as_ref().to_path_buf(),
+            size: (HIRES_WIDTH * scale, HIRES_HEIGHT * scale),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Frames added so far.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn add_frame(&mut self, framebuffer: &Framebuffer) -> Result<(), Chip8Error> {
+        if self.output.is_none() {
+            self.output = Some(self.create_output()?);
+        }
+
+        let colors = self.colors(framebuffer);
+        let frame = self.frames;
+        let (width, height) = self.size;
+
+        match self.output.as_mut() {
+            Some(Output::Gif {
+                encoder,
+                size,
+                pending,
+            }) => match pending.take() {
+                Some((pending_colors, start)) if pending_colors == colors => {
+                    *pending = Some((pending_colors, start));
+                }
+                Some((_, start)) if gif_time(frame) - gif_time(start) < GIF_MIN_DELAY => {
+                    *pending = Some((colors, start));
+                }
+                Some((pending_colors, start)) => {
+                    let delay = gif_time(frame) - gif_time(start);
+                    write_gif_frame(encoder, *size, pending_colors, delay, &self.path)?;
+                    *pending = Some((colors, frame));
+                }
+                None => *pending = Some((colors, frame)),
+            },
+            Some(Output::Frames { directory }) => {
+                let pixels: Vec<u8> = colors
+                    .iter()
+                    .flat_map(|color| {
+                        let (r, g, b) = self.palette.color(*color);
+                        [r, g, b]
+                    })
+                    .collect();
+
+                let path = directory.join(format!("{:06}.png", frame + 1));
+                let png = screenshot::encode_png(width, height, &pixels);
+
+                fs::write(&path, png).map_err(|source| Chip8Error::Io { path, source })?;
+            }
+            None => (),
+        }
+
+        self.frames += 1;
+
+        Ok(())
+    }
+
+    /// Writes what's left, the GIF is only complete once finished.
+    pub fn finish(mut self) -> Result<(), Chip8Error> {
+        if let Some(Output::Gif {
+            mut encoder,
+            size,
+            pending,
+        }) = self.output.take()
+        {
+            if let Some((colors, start)) = pending {
+                let delay = (gif_time(self.frames) - gif_time(start)).max(GIF_MIN_DELAY);
+                write_gif_frame(&mut encoder, size, colors, delay, &self.path)?;
+            }
+
+            encoder
+                .into_inner()
+                .and_then(|mut writer| std::io::Write::flush(&mut writer))
+                .map_err(|source| self.io_error(source))?;
+        }
+
+        Ok(())
+    }
+
+    fn create_output(&self) -> Result<Output, Chip8Error> {
+        if self
+            .path
+            .extension()
+            .is_some_and(|extension| extension == "gif")
+        {
+            let (width, height) = self.size;
+            let size = u16::try_from(width)
+                .ok()
+                .zip(u16::try_from(height).ok())
+                .ok_or_else(|| {
+                    Chip8Error::Device(format!(
+                        "{}: {width}x{height} is too large for a GIF",
+                        self.path.display()
+                    ))
+                })?;
+
+            let file = File::create(&self.path).map_err(|source| self.io_error(source))?;
+
+            let palette: Vec<u8> = self
+                .palette
+                .colors
+                .iter()
+                .flat_map(|(r, g, b)| [*r, *g, *b])
+                .collect();
+
+            let mut encoder = gif::Encoder::new(BufWriter::new(file), size.0, size.1, &palette)
+                .map_err(|e| gif_error(e, &self.path))?;
+            encoder
+                .set_repeat(gif::Repeat::Infinite)
+                .map_err(|e| gif_error(e, &self.path))?;
+
+            Ok(Output::Gif {
+                encoder,
+                size,
+                pending: None,
+            })
+        } else {
+            fs::create_dir_all(&self.path).map_err(|source| self.io_error(source))?;
+
+            Ok(Output::Frames {
+                directory: self.path.clone(),
+            })
+        }
+    }
+
+    // Palette index of every pixel of the recording, the framebuffer scaled up to its size
+    fn colors(&self, framebuffer: &Framebuffer) -> Vec<u8> {
+        let (width, height) = self.size;
+        let (source_width, source_height) = (framebuffer.width(), framebuffer.height());
+
+        (0..height)
+            .flat_map(|y| {
+                (0..width).map(move |x| {
+                    framebuffer.color(x * source_width / width, y * source_height / height)
+                })
+            })
+            .collect()
+    }
+
+    fn io_error(&self, source: std::io::Error) -> Chip8Error {
+        Chip8Error::Io {
+            path: self.path.clone(),
+            source,
+        }
+    }
+}
+
+// Hundredths of a second from the start of the recording to the start of `frame`
+fn gif_time(frame: u64) -> u64 {
+    frame * 100 / FRAME_RATE as u64
+}
+
+fn write_gif_frame(
+    encoder: &mut gif::Encoder<BufWriter<File>>,
+    (width, height): (u16, u16),
+    colors: Vec<u8>,
+    delay: u64,
+    path: &Path,
+) -> Result<(), Chip8Error> {
+    let frame = gif::Frame {
+        width,
+        height,
+        delay: delay.min(u16::MAX as u64) as u16,
+        buffer: colors.into(),
+        ..gif::Frame::default()
+    };
+
+    encoder.write_frame(&frame).map_err(|e| gif_error(e, path))
+}
+
+fn gif_error(error: gif::EncodingError, path: &Path) -> Chip8Error {
+    match error {
+        gif::EncodingError::Io(source) => Chip8Error::Io {
+            path: path.to_path_buf(),
+            source,
+        },
+        e => Chip8Error::Device(format!("{}: {e}", path.display())),
+    }
+}
@@ -0,0 +1,42 @@
+// xorshift64*, small enough that its whole state fits in a save state
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new() -> Rng {
+        Rng::from_state(rand::random())
+    }
+
+    // The seed goes through splitmix64 first so that close seeds give unrelated sequences
+    pub fn from_seed(seed: u64) -> Rng {
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        Rng::from_state(z ^ (z >> 31))
+    }
+
+    pub fn from_state(state: u64) -> Rng {
+        // Zero is the one state xorshift never leaves
+        let state = if state == 0 {
+            0x9E37_79B9_7F4A_7C15
+        } else {
+            state
+        };
+
+        Rng { state }
+    }
+
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_byte(&mut self) -> u8 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+}
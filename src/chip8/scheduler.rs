@@ -0,0 +1,54 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::FRAME_RATE;
+
+// A late frame is caught up by running several in a row, past this many the emulator gives up and
+// slows down instead of freezing the screen while it catches up
+const MAX_FRAMES_BEHIND: u64 = 4;
+
+// Fixed timestep on a monotonic clock: frame N is due N/60th of a second after the start. Frame
+// deadlines are computed from the start instead of adding up frame durations, so there's no drift.
+pub(super) struct Scheduler {
+    start: Instant,
+    frames: u64,
+}
+
+impl Scheduler {
+    pub(super) fn new() -> Scheduler {
+        Scheduler {
+            start: Instant::now(),
+            frames: 0,
+        }
+    }
+
+    /// Number of frames to emulate now to be back on time, usually 1.
+    pub(super) fn frames_due(&mut self) -> u64 {
+        let elapsed = self.start.elapsed();
+        let due = (elapsed.as_nanos() * FRAME_RATE as u128 / 1_000_000_000) as u64 + 1;
+
+        if due <= self.frames {
+            return 0;
+        }
+
+        // Frames that can't be caught up are dropped, the next ones are on time again
+        self.frames = self.frames.max(due.saturating_sub(MAX_FRAMES_BEHIND));
+
+        let frames = due - self.frames;
+        self.frames = due;
+
+        frames
+    }
+
+    pub(super) fn sleep_until_next_frame(&self) {
+        let deadline = self.start + Scheduler::frame_time(self.frames);
+
+        thread::sleep(deadline.saturating_duration_since(Instant::now()));
+    }
+
+    fn frame_time(frame: u64) -> Duration {
+        Duration::from_nanos((frame as u128 * 1_000_000_000 / FRAME_RATE as u128) as u64)
+    }
+}
@@ -0,0 +1,72 @@
+use super::{common::fnv1a, Framebuffer};
+
+// Characters for the 4 colors of the palette, the last 2 only show up with XO-CHIP planes
+const COLOR_CHARACTERS: [char; 4] = ['.', '#', 'o', '@'];
+
+/// The screen as text, one line per row and one character per pixel: `.` when off and `#` when
+/// on. This is the format of golden images.
+pub fn screen_text(framebuffer: &Framebuffer) -> String {
+    let mut text = String::new();
+
+    for y in 0..framebuffer.height() {
+        for x in 0..framebuffer.width() {
+            text.push(COLOR_CHARACTERS[framebuffer.color(x, y) as usize & 0b11]);
+        }
+
+        text.push('\n');
+    }
+
+    text
+}
+
+/// Hash of `screen_text`, a shorter alternative to golden images.
+pub fn screen_hash(framebuffer: &Framebuffer) -> u64 {
+    fnv1a(screen_text(framebuffer).as_bytes())
+}
+
+/// `None` when both screens are the same, otherwise the actual screen with every wrong pixel
+/// replaced by `+` when it should be off, `-` when it should be on and `X` for a wrong color.
+pub fn screen_diff(expected: &str, actual: &str) -> Option<String> {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    if expected == actual {
+        return None;
+    }
+
+    let expected_size = (expected.first().map_or(0, |row| row.len()), expected.len());
+    let actual_size = (actual.first().map_or(0, |row| row.len()), actual.len());
+
+    if expected_size != actual_size {
+        return Some(format!(
+            "Expected a {}x{} screen but got {}x{}.\n",
+            expected_size.0, expected_size.1, actual_size.0, actual_size.1
+        ));
+    }
+
+    let mut diff = String::new();
+    let mut wrong_pixels = 0;
+
+    for (expected_row, actual_row) in expected.iter().zip(&actual) {
+        for (expected, actual) in expected_row.chars().zip(actual_row.chars()) {
+            let character = match (expected, actual) {
+                _ if expected == actual => actual,
+                ('.', _) => '+',
+                (_, '.') => '-',
+                _ => 'X',
+            };
+
+            if character != actual {
+                wrong_pixels += 1;
+            }
+
+            diff.push(character);
+        }
+
+        diff.push('\n');
+    }
+
+    diff.push_str(&format!("{wrong_pixels} pixel(s) differ.\n"));
+
+    Some(diff)
+}
@@ -0,0 +1,52 @@
+use std::{fs, path::Path};
+
+use super::{Chip8Error, Framebuffer, Palette};
+
+/// The screen as a PNG image in the colours of `palette`, every pixel drawn as a `scale` by
+/// `scale` square. A scale of 0 is taken as 1.
+pub fn screenshot_png(framebuffer: &Framebuffer, palette: &Palette, scale: usize) -> Vec<u8> {
+    let scale = scale.max(1);
+    let (width, height) = (framebuffer.width() * scale, framebuffer.height() * scale);
+
+    let mut pixels = Vec::with_capacity(width * height * 3);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = palette.color(framebuffer.color(x / scale, y / scale));
+            pixels.extend([r, g, b]);
+        }
+    }
+
+    encode_png(width, height, &pixels)
+}
+
+// `pixels` holds the red, green and blue bytes of each pixel, row by row
+pub(super) fn encode_png(width: usize, height: usize, pixels: &[u8]) -> Vec<u8> {
+    let mut png = vec![];
+    let mut encoder = png::Encoder::new(&mut png, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    // Writing to memory can't fail and the image has the size given to the encoder
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(pixels))
+        .expect("PNG encoding into memory");
+
+    png
+}
+
+/// Writes `screenshot_png` to a file.
+pub fn save_screenshot(
+    path: impl AsRef<Path>,
+    framebuffer: &Framebuffer,
+    palette: &Palette,
+    scale: usize,
+) -> Result<(), Chip8Error> {
+    let path = path.as_ref();
+
+    fs::write(path, screenshot_png(framebuffer, palette, scale)).map_err(|source| Chip8Error::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
@@ -0,0 +1,106 @@
+use std::{fmt, str::FromStr};
+
+use super::cpu::Instruction;
+
+// The VIP's CDP1802 runs at 1.76064 MHz and takes 8 clock cycles per machine cycle, which gives
+// 3668 machine cycles between two 60Hz interrupts
+pub const VIP_CYCLES_PER_FRAME: i64 = 3668;
+
+// The display DMA steals a machine cycle for each of the 8 bytes of the 128 scanlines shown, and
+// the interrupt routine updating the timers takes around 40 more
+const VIP_DISPLAY_CYCLES: i64 = 128 * 8 + 40;
+
+/// Machine cycles left to the interpreter in each frame.
+pub const VIP_INTERPRETER_CYCLES: i64 = VIP_CYCLES_PER_FRAME - VIP_DISPLAY_CYCLES;
+
+// How many instructions run in a frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Timing {
+    /// Every instruction takes the same time, the clock rate sets how many run per frame.
+    #[default]
+    Instructions,
+    /// Instructions cost the machine cycles they took on the COSMAC VIP interpreter and DXYN waits
+    /// for the next interrupt before drawing, the clock rate is ignored.
+    CosmacVip,
+}
+
+impl Timing {
+    pub const ALL: [Timing; 2] = [Timing::Instructions, Timing::CosmacVip];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Timing::Instructions => "instructions",
+            Timing::CosmacVip => "vip",
+        }
+    }
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Timing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Timing::ALL
+            .into_iter()
+            .find(|timing| timing.name() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = Timing::ALL.iter().map(|t| t.name()).collect();
+
+                format!(
+                    "Unknown timing '{}', expected one of: {}.",
+                    s.trim(),
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Machine cycles the VIP interpreter spends on an instruction, fetch and decode included.
+/// `skipped` tells whether a conditional skip was taken, which costs a few more cycles.
+///
+/// The costs follow the CHIP-8 interpreter listing in the RCA COSMAC VIP manual (VIP-311): the
+/// 1802 instructions of the fetch and dispatch loop plus those of the routine handling the opcode,
+/// at 2 machine cycles for short instructions and 3 for long branches. Jackson Sommerich tabulates
+/// the same counts in "Chip-8 Instruction Scheduling and Frequency". A few are simplified:
+///
+/// - All the 8XYN share one cost, the VIP runs them by writing a 2 byte 1802 routine into memory
+///   and calling it, which takes the same time whatever the operation.
+/// - DXYN counts the drawing only, not the wait for the interrupt. Every row is charged as a
+///   sprite that isn't byte aligned, shifted in place and XORed as 2 bytes. Aligned sprites and
+///   rows landing off the screen are a little cheaper on the VIP.
+/// - FX33 divides by repeated subtractions, its cost depends on the digits and this is the
+///   average over all the byte values.
+/// - BNNN and FX1E ignore the 2 cycles added when the address crosses a page.
+/// - The remaining instructions, including those the VIP doesn't have, cost 10 cycles like 7XKK.
+pub(super) fn vip_cycles(instruction: &Instruction, skipped: bool) -> i64 {
+    let skip = if skipped { 4 } else { 0 };
+
+    match instruction.nibbles {
+        (0x0, 0x0, 0xE, 0x0) => 24,
+        (0x0, 0x0, 0xE, 0xE) => 10,
+        (0x1, _, _, _) => 12,
+        (0x2, _, _, _) => 26,
+        (0x3, _, _, _) | (0x4, _, _, _) => 10 + skip,
+        (0x5, _, _, _) | (0x9, _, _, _) => 14 + skip,
+        (0x6, _, _, _) => 6,
+        (0x7, _, _, _) => 10,
+        (0x8, _, _, _) => 44,
+        (0xA, _, _, _) => 12,
+        (0xB, _, _, _) => 22,
+        (0xC, _, _, _) => 36,
+        // Each row is shifted in place and XORed as 2 bytes
+        (0xD, _, _, n) => 26 + 68 * n as i64,
+        (0xE, _, _, _) => 14 + skip,
+        (0xF, _, 0x1, 0xE) => 18,
+        (0xF, _, 0x2, 0x9) => 20,
+        // Divides by repeated subtractions
+        (0xF, _, 0x3, 0x3) => 204,
+        (0xF, x, 0x5, 0x5) | (0xF, x, 0x6, 0x5) => 14 + 14 * (x as i64 + 1),
+        _ => 10,
+    }
+}
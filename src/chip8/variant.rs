@@ -0,0 +1,65 @@
+use std::{fmt, str::FromStr};
+
+const RAM_SIZE_IN_BYTES: usize = 4096;
+const XO_CHIP_RAM_SIZE_IN_BYTES: usize = 65536;
+
+// Each variant is a superset of the previous one
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Variant {
+    Chip8,
+    #[default]
+    SuperChip,
+    XoChip,
+}
+
+impl Variant {
+    pub const ALL: [Variant; 3] = [Variant::Chip8, Variant::SuperChip, Variant::XoChip];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Chip8 => "chip8",
+            Variant::SuperChip => "schip",
+            Variant::XoChip => "xochip",
+        }
+    }
+
+    pub fn ram_size(&self) -> usize {
+        match self {
+            Variant::Chip8 | Variant::SuperChip => RAM_SIZE_IN_BYTES,
+            Variant::XoChip => XO_CHIP_RAM_SIZE_IN_BYTES,
+        }
+    }
+
+    pub fn supports_superchip(&self) -> bool {
+        *self != Variant::Chip8
+    }
+
+    pub fn supports_xochip(&self) -> bool {
+        *self == Variant::XoChip
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Variant {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Variant::ALL
+            .into_iter()
+            .find(|variant| variant.name() == s.trim().to_lowercase())
+            .ok_or_else(|| {
+                let names: Vec<&str> = Variant::ALL.iter().map(|v| v.name()).collect();
+
+                format!(
+                    "Unknown variant '{}', expected one of: {}.",
+                    s.trim(),
+                    names.join(", ")
+                )
+            })
+    }
+}
@@ -0,0 +1,41 @@
+use std::fmt;
+
+// Conditions that stop execution in the debugger, checked as instructions access memory and
+// registers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Watchpoint {
+    MemoryRead(u16),
+    MemoryWrite(u16),
+    Register { index: usize, value: u8 },
+    IndexInRange { start: u16, end: u16 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WatchHit {
+    pub watchpoint: Watchpoint,
+    /// Address of the instruction that triggered the watchpoint.
+    pub address: u16,
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Watchpoint::MemoryRead(address) => write!(f, "read of {:#06X}", address),
+            Watchpoint::MemoryWrite(address) => write!(f, "write to {:#06X}", address),
+            Watchpoint::Register { index, value } => write!(f, "V{:X} == {:#04X}", index, value),
+            Watchpoint::IndexInRange { start, end } => {
+                write!(f, "I in {:#06X}..={:#06X}", start, end)
+            }
+        }
+    }
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} by instruction at {:#06X}",
+            self.watchpoint, self.address
+        )
+    }
+}
@@ -1,12 +1,275 @@
-use clap::{self, Parser};
+use std::{fs, path::Path};
+
+use chip_8_rs::{
+    Backend, FaultPolicy, Palette, QuirksPreset, Timing, Variant, MAX_CLOCK_RATE,
+    MAX_SCREENSHOT_SCALE,
+};
+use clap::{self, Parser, Subcommand};
 
 #[derive(clap::Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[arg(short = 'p', long = "program_path", default_value_t = String::from("programs/test_opcode.ch8"))]
     pub program_path: String,
 
+    /// Instructions executed per second, 3000 by default and up to 1000000000
+    #[arg(
+        long = "clock-rate",
+        conflicts_with = "instructions_per_frame",
+        value_parser = clap::value_parser!(u32).range(..=MAX_CLOCK_RATE as i64)
+    )]
+    pub clock_rate: Option<u32>,
+
+    /// Clock rate given as instructions per frame, at 60 frames per second
+    #[arg(short = 'i', long = "instructions_per_frame")]
+    pub instructions_per_frame: Option<usize>,
+
+    /// Where the screen, sound and keyboard are: sdl (a window), tty (the terminal) or headless.
+    /// Defaults to sdl, or headless when built without SDL
+    #[arg(long = "frontend", conflicts_with = "headless")]
+    pub frontend: Option<Frontend>,
+
+    /// Same as --frontend headless
+    #[arg(long = "headless")]
+    pub headless: bool,
+
+    /// Draw with braille characters in the terminal, 8 pixels per character instead of 2
+    #[arg(long = "braille")]
+    pub braille: bool,
+
+    /// Don't ring the terminal bell when the program beeps
+    #[arg(long = "no-bell")]
+    pub no_bell: bool,
+
+    /// Colours of the screen in hex, e.g. 0F380F,9BBC0F, with 2 more for XO-CHIP's bitplanes
+    #[arg(long = "palette")]
+    pub palette: Option<Palette>,
+
+    /// Size of a pixel in the screenshots taken with F12 and the screen recordings with F10, up
+    /// to 511
+    #[arg(long = "screenshot-scale", default_value_t = 1, value_parser = parse_screenshot_scale)]
+    pub screenshot_scale: usize,
+
+    /// Quirks preset: vip, chip48, schip or modern. Defaults to the one named in a `.quirks` file
+    /// next to the program, or modern
+    #[arg(short = 'q', long = "quirks")]
+    pub quirks: Option<QuirksPreset>,
+
+    /// Start paused in the debugger, commands are read from stdin
+    #[arg(short = 'd', long = "debug")]
+    pub debug: bool,
+
+    /// Add a debugger breakpoint on this address (hexadecimal), can be repeated
+    #[arg(short = 'b', long = "break")]
+    pub breakpoints: Vec<String>,
+
+    /// Seed for the random numbers of CXKK, runs with the same seed and inputs are identical
+    #[arg(long = "seed")]
+    pub seed: Option<u64>,
+
+    /// Record every input in a movie file, written when the emulator stops
+    #[arg(long = "record", conflicts_with_all = ["replay", "load_state"])]
+    pub record: Option<String>,
+
+    /// Play the inputs of a movie file recorded with --record
+    #[arg(long = "replay", conflicts_with = "load_state")]
+    pub replay: Option<String>,
+
+    /// Save state to load once the program is in memory
+    #[arg(long = "load-state")]
+    pub load_state: Option<String>,
+
+    /// Machine to emulate: chip8, schip or xochip
+    #[arg(long = "variant", default_value_t = Variant::default())]
+    pub variant: Variant,
+
+    /// What a stack overflow or underflow does: halt with a report, ignore or wrap
+    #[arg(long = "fault-policy", default_value_t = FaultPolicy::default())]
+    pub fault_policy: FaultPolicy,
+
+    /// Fault on unknown opcodes and 0NNN machine code calls instead of skipping them
+    #[arg(long = "strict")]
+    pub strict: bool,
+
+    /// How many instructions run per frame: instructions (the clock rate) or vip (the machine
+    /// cycles of the COSMAC VIP)
+    #[arg(long = "timing", default_value_t = Timing::default())]
+    pub timing: Timing,
+
+    /// How frames run: interpreter, or blocks (translated blocks of code, faster with the same
+    /// results)
+    #[arg(long = "backend", default_value_t = Backend::default())]
+    pub backend: Backend,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Print the instructions of a ROM instead of running it
+    Disasm {
+        rom_path: String,
+
+        /// Machine whose instructions are decoded: chip8, schip or xochip
+        #[arg(long = "variant", default_value_t = Variant::default())]
+        variant: Variant,
+    },
+    /// Build a ROM from an assembly source file
+    Asm {
+        source_path: String,
+
+        /// Where the ROM is written, defaults to the source path with a `.ch8` extension
+        #[arg(short = 'o', long = "output")]
+        output_path: Option<String>,
+    },
+    /// Run a ROM without any window for a number of frames and check the screen it leaves
+    Test(TestArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct TestArgs {
+    pub rom_path: String,
+
+    /// Frames to run, or the most frames to wait for --until-pc or --until-opcode
+    #[arg(long = "frames", default_value_t = 60)]
+    pub frames: u64,
+
+    /// Stop before executing the instruction at this address (hexadecimal)
+    #[arg(long = "until-pc")]
+    pub until_pc: Option<String>,
+
+    /// Stop before executing this opcode (hexadecimal), e.g. 1234
+    #[arg(long = "until-opcode")]
+    pub until_opcode: Option<String>,
+
+    /// Text file the screen must match, `.` for pixels off and `#` for pixels on
+    #[arg(long = "golden")]
+    pub golden_path: Option<String>,
+
+    /// Hash the screen must have, as printed when neither --golden nor --hash is given
+    #[arg(long = "hash")]
+    pub hash: Option<String>,
+
+    /// Write the screen to the --golden file instead of comparing it
+    #[arg(long = "update", requires = "golden_path")]
+    pub update: bool,
+
+    /// Save the screen as a PNG image once this many frames have run, can be repeated. Runs
+    /// more than --frames frames when needed
+    #[arg(long = "screenshot-at-frame", num_args = 2, value_names = ["N", "PATH"])]
+    pub screenshots: Vec<String>,
+
+    /// Record every frame run to an animated GIF when the path ends with .gif, or to numbered PNG
+    /// images in the directory otherwise
+    #[arg(long = "record-screen")]
+    pub record_screen: Option<String>,
+
+    /// Colours of the screenshots and the screen recording in hex, e.g. 0F380F,9BBC0F
+    #[arg(long = "palette")]
+    pub palette: Option<Palette>,
+
+    /// Size of a pixel in the screenshots and the screen recording, up to 511
+    #[arg(long = "screenshot-scale", default_value_t = 1, value_parser = parse_screenshot_scale)]
+    pub screenshot_scale: usize,
+
+    /// Instructions executed per second, 3000 by default and up to 1000000000
+    #[arg(
+        long = "clock-rate",
+        conflicts_with = "instructions_per_frame",
+        value_parser = clap::value_parser!(u32).range(..=MAX_CLOCK_RATE as i64)
+    )]
+    pub clock_rate: Option<u32>,
+
+    /// Clock rate given as instructions per frame, at 60 frames per second
     #[arg(short = 'i', long = "instructions_per_frame")]
     pub instructions_per_frame: Option<usize>,
+
+    /// Quirks preset: vip, chip48, schip or modern
+    #[arg(short = 'q', long = "quirks", default_value_t = QuirksPreset::Modern)]
+    pub quirks: QuirksPreset,
+
+    /// Seed for CXKK, tests are reproducible with a fixed one
+    #[arg(long = "seed", default_value_t = 0)]
+    pub seed: u64,
+
+    /// Machine to emulate: chip8, schip or xochip
+    #[arg(long = "variant", default_value_t = Variant::default())]
+    pub variant: Variant,
+
+    /// What a stack overflow or underflow does: halt with a report, ignore or wrap
+    #[arg(long = "fault-policy", default_value_t = FaultPolicy::default())]
+    pub fault_policy: FaultPolicy,
+
+    /// Fault on unknown opcodes and 0NNN machine code calls instead of skipping them
+    #[arg(long = "strict")]
+    pub strict: bool,
+
+    /// How many instructions run per frame: instructions (the clock rate) or vip (the machine
+    /// cycles of the COSMAC VIP)
+    #[arg(long = "timing", default_value_t = Timing::default())]
+    pub timing: Timing,
+
+    /// How frames run: interpreter, or blocks (translated blocks of code, faster with the same
+    /// results)
+    #[arg(long = "backend", default_value_t = Backend::default())]
+    pub backend: Backend,
+}
+
+impl TestArgs {
+    // --screenshot-at-frame values come in pairs: the frame and the path
+    pub fn screenshots(&self) -> Result<Vec<(u64, String)>, String> {
+        self.screenshots
+            .chunks(2)
+            .map(|pair| {
+                let frame = pair[0]
+                    .parse()
+                    .map_err(|_| format!("Invalid screenshot frame '{}'.", pair[0]))?;
+
+                Ok((frame, pair[1].clone()))
+            })
+            .collect()
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frontend {
+    Sdl,
+    Tty,
+    Headless,
+}
+
+impl Args {
+    pub fn frontend(&self) -> Frontend {
+        match self.frontend {
+            Some(frontend) => frontend,
+            None if self.headless || !cfg!(feature = "sdl") => Frontend::Headless,
+            None => Frontend::Sdl,
+        }
+    }
+
+    pub fn quirks_preset(&self) -> Result<QuirksPreset, String> {
+        if let Some(preset) = self.quirks {
+            return Ok(preset);
+        }
+
+        // programs/game.ch8 can ship with a programs/game.quirks file containing the preset name
+        let quirks_path = Path::new(&self.program_path).with_extension("quirks");
+
+        match fs::read_to_string(&quirks_path) {
+            Ok(preset) => preset
+                .parse()
+                .map_err(|e| format!("{}: {e}", quirks_path.display())),
+            Err(_) => Ok(QuirksPreset::Modern),
+        }
+    }
+}
+
+fn parse_screenshot_scale(s: &str) -> Result<usize, String> {
+    s.parse()
+        .ok()
+        .filter(|scale| (1..=MAX_SCREENSHOT_SCALE).contains(scale))
+        .ok_or_else(|| format!("expected a scale from 1 to {MAX_SCREENSHOT_SCALE}"))
 }
 
 pub fn parse_args() -> Args {
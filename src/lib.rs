@@ -0,0 +1,11 @@
+mod chip8;
+
+pub use chip8::{
+    assemble, assemble_file, devices, disassemble, disassemble_instruction, frontends,
+    save_screenshot, screen_diff, screen_hash, screen_text, screenshot_png, Backend, Chip8,
+    Chip8Error, Debugger, DisassembledLine, Disassembly, Emulator, FaultPolicy, Framebuffer,
+    History, Movie, MovieEvent, MovieInput, Palette, Quirks, QuirksPreset, Rgb, ScreenRecorder,
+    Timing, Variant, WatchHit, Watchpoint, BIG_FONT_DATA, DEFAULT_CLOCK_RATE,
+    DEFAULT_INSTRUCTIONS_PER_FRAME, FONT_DATA, FRAME_RATE, HEIGHT, HIRES_HEIGHT, HIRES_WIDTH,
+    MAX_CLOCK_RATE, MAX_SCREENSHOT_SCALE, VIP_INTERPRETER_CYCLES, WIDTH,
+};
@@ -1,27 +1,368 @@
-mod chip8;
 mod cli;
 
-use std::process;
+use std::{error::Error, fs, path::Path, process};
 
-use crate::chip8::Emulator;
+use chip_8_rs::{
+    assemble_file,
+    devices::{Display, Input, Speaker},
+    disassemble,
+    frontends::Headless,
+    save_screenshot, screen_diff, screen_hash, screen_text, Chip8, Chip8Error, Debugger, Emulator,
+    Movie, Quirks, ScreenRecorder, Variant,
+};
 
 fn main() {
+    let args = cli::parse_args();
+
+    match &args.command {
+        Some(cli::Command::Disasm { rom_path, variant }) => {
+            disasm(rom_path, *variant);
+            return;
+        }
+        Some(cli::Command::Asm {
+            source_path,
+            output_path,
+        }) => {
+            asm(source_path, output_path.as_deref());
+            return;
+        }
+        Some(cli::Command::Test(test_args)) => {
+            test(test_args);
+            return;
+        }
+        None => (),
+    }
+
     println!("Chip 8 emulator");
 
-    let args = cli::parse_args();
+    // Set up before the frontend, the tty one takes over the terminal
+    let chip8 = machine(&args);
+
+    match args.frontend() {
+        #[cfg(feature = "sdl")]
+        cli::Frontend::Sdl => {
+            let (display, speaker, input) =
+                match chip_8_rs::frontends::sdl::init(args.palette.unwrap_or_default()) {
+                    Ok(devices) => devices,
+                    Err(e) => {
+                        eprintln!("Error while initializing SDL: {e}");
+                        process::exit(1);
+                    }
+                };
+
+            run(&args, chip8, display, speaker, input);
+        }
+        #[cfg(feature = "tty")]
+        cli::Frontend::Tty => {
+            // The debugger reads its commands from the terminal the screen is drawn in
+            if args.debug || !args.breakpoints.is_empty() {
+                eprintln!("The debugger can't be used with the tty frontend.");
+                process::exit(1);
+            }
+
+            let (display, speaker, input) = match chip_8_rs::frontends::tty::init(
+                args.braille,
+                !args.no_bell,
+                args.palette.unwrap_or_default(),
+            ) {
+                Ok(devices) => devices,
+                Err(e) => {
+                    eprintln!("Error while initializing the terminal: {e}");
+                    process::exit(1);
+                }
+            };
+
+            run(&args, chip8, display, speaker, input);
+        }
+        cli::Frontend::Headless => run(&args, chip8, Headless, Headless, Headless),
+        #[allow(unreachable_patterns)]
+        frontend => {
+            eprintln!("This build doesn't include the {frontend:?} frontend.");
+            process::exit(1);
+        }
+    }
+}
+
+fn machine(args: &cli::Args) -> Chip8 {
+    let mut chip8 = Chip8::with_variant(args.variant);
+
+    if let Some(instructions_per_frame) = args.instructions_per_frame {
+        if let Err(e) = chip8.set_instructions_per_frame(instructions_per_frame) {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    }
 
-    let sdl_context = match sdl2::init() {
-        Ok(sdl_context) => sdl_context,
+    if let Some(clock_rate) = args.clock_rate {
+        chip8.set_clock_rate(clock_rate);
+    }
+
+    if let Some(seed) = args.seed {
+        chip8.set_seed(seed);
+    }
+
+    let quirks_preset = match args.quirks_preset() {
+        Ok(quirks_preset) => quirks_preset,
         Err(e) => {
-            eprintln!("Error while initializing SDL: {e}");
+            eprintln!("Error while reading quirks: {e}");
             process::exit(1);
         }
     };
 
-    let mut emulator = Emulator::new(args.instructions_per_frame, &sdl_context);
+    println!("Using {quirks_preset} quirks.");
+
+    chip8.set_quirks(Quirks::from_preset(quirks_preset));
+    chip8.set_fault_policy(args.fault_policy);
+    chip8.set_strict(args.strict);
+    chip8.set_timing(args.timing);
+    chip8.set_backend(args.backend);
+
+    chip8
+}
 
-    if let Err(e) = emulator.run(&args.program_path) {
+fn run<D: Display, S: Speaker, I: Input>(
+    args: &cli::Args,
+    chip8: Chip8,
+    display: D,
+    speaker: S,
+    input: I,
+) {
+    let mut emulator = Emulator::new(chip8, display, speaker, input);
+    emulator.set_screenshot_scale(args.screenshot_scale);
+
+    if let Some(state_path) = &args.load_state {
+        emulator.set_startup_state(state_path);
+    }
+
+    if let Some(movie_path) = &args.record {
+        // The movie needs a seed to be replayed, pick one when none was given
+        emulator.record_movie(movie_path, args.seed.unwrap_or_else(rand::random));
+    }
+
+    if let Some(movie_path) = &args.replay {
+        let movie = fs::read_to_string(movie_path)
+            .map_err(|e| e.to_string())
+            .and_then(|movie| movie.parse::<Movie>());
+
+        match movie {
+            Ok(movie) => emulator.replay_movie(movie),
+            Err(e) => {
+                eprintln!("Error while reading movie {movie_path}: {e}");
+                process::exit(1);
+            }
+        }
+    }
+
+    if args.debug || !args.breakpoints.is_empty() {
+        let mut debugger = Debugger::new();
+
+        for breakpoint in &args.breakpoints {
+            match Debugger::parse_address(Some(breakpoint)) {
+                Ok(address) => debugger.add_breakpoint(address),
+                Err(e) => {
+                    eprintln!("Error while adding breakpoint: {e}");
+                    process::exit(1);
+                }
+            }
+        }
+
+        debugger.set_paused(args.debug);
+
+        println!("Debugger enabled, press F1 to break and type 'help' for a list of commands.");
+
+        emulator.set_debugger(debugger);
+    }
+
+    let result = emulator.run(&args.program_path);
+
+    // The frontend gives the terminal back before the error is printed
+    drop(emulator);
+
+    if let Err(e) = result {
         eprintln!("Chip-8 error: {e}");
         process::exit(1)
     }
 }
+
+fn save_screenshots(
+    chip8: &Chip8,
+    screenshots: &[(u64, String)],
+    frame: u64,
+    args: &cli::TestArgs,
+) -> Result<(), Chip8Error> {
+    let palette = args.palette.unwrap_or_default();
+
+    for (_, path) in screenshots.iter().filter(|(at, _)| *at == frame) {
+        save_screenshot(path, chip8.framebuffer(), &palette, args.screenshot_scale)?;
+        println!("Saved screenshot of frame {frame} to {path}.");
+    }
+
+    Ok(())
+}
+
+fn disasm(rom_path: &str, variant: Variant) {
+    let rom = match fs::read(rom_path) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!("Error while reading {rom_path}: {e}");
+            process::exit(1);
+        }
+    };
+
+    print!("{}", disassemble(&rom, variant));
+}
+
+fn asm(source_path: &str, output_path: Option<&str>) {
+    let rom = match assemble_file(source_path) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!("Assembly error: {e}");
+            process::exit(1);
+        }
+    };
+
+    let output_path = match output_path {
+        Some(output_path) => Path::new(output_path).to_path_buf(),
+        None => Path::new(source_path).with_extension("ch8"),
+    };
+
+    if let Err(e) = fs::write(&output_path, &rom) {
+        eprintln!("Error while writing {}: {e}", output_path.display());
+        process::exit(1);
+    }
+
+    println!("Wrote {} bytes to {}.", rom.len(), output_path.display());
+}
+
+fn test(args: &cli::TestArgs) {
+    match run_test(args) {
+        Ok(true) => (),
+        Ok(false) => process::exit(1),
+        Err(e) => {
+            eprintln!("Test error: {e}");
+            process::exit(2);
+        }
+    }
+}
+
+// Returns false when the screen is not the expected one
+fn run_test(args: &cli::TestArgs) -> Result<bool, Box<dyn Error>> {
+    let rom = fs::read(&args.rom_path).map_err(|e| format!("{}: {e}", args.rom_path))?;
+
+    let mut chip8 = Chip8::with_variant(args.variant);
+    chip8.set_quirks(Quirks::from_preset(args.quirks));
+    chip8.set_seed(args.seed);
+    chip8.set_fault_policy(args.fault_policy);
+    chip8.set_strict(args.strict);
+    chip8.set_timing(args.timing);
+    chip8.set_backend(args.backend);
+
+    if let Some(instructions_per_frame) = args.instructions_per_frame {
+        chip8.set_instructions_per_frame(instructions_per_frame)?;
+    }
+
+    if let Some(clock_rate) = args.clock_rate {
+        chip8.set_clock_rate(clock_rate);
+    }
+
+    chip8.load_rom(&rom)?;
+
+    let until_pc = args
+        .until_pc
+        .as_deref()
+        .map(|address| Debugger::parse_address(Some(address)))
+        .transpose()?;
+    let until_opcode = args
+        .until_opcode
+        .as_deref()
+        .map(|opcode| Debugger::parse_address(Some(opcode)))
+        .transpose()?;
+
+    let screenshots = args.screenshots()?;
+    let last_screenshot = screenshots.iter().map(|(frame, _)| *frame).max();
+
+    let mut frames = 0;
+    let mut reached = false;
+
+    save_screenshots(&chip8, &screenshots, frames, args)?;
+
+    let mut recorder = args.record_screen.as_ref().map(|path| {
+        ScreenRecorder::new(
+            path,
+            args.palette.unwrap_or_default(),
+            args.screenshot_scale,
+        )
+    });
+
+    while frames < args.frames.max(last_screenshot.unwrap_or(0)) && !reached && !chip8.is_halted() {
+        reached = chip8.run_frame_until(|chip8| {
+            Some(chip8.pc()) == until_pc
+                || (until_opcode.is_some() && chip8.opcode_at(chip8.pc()) == until_opcode)
+        })?;
+
+        frames += 1;
+
+        save_screenshots(&chip8, &screenshots, frames, args)?;
+
+        if let Some(recorder) = &mut recorder {
+            recorder.add_frame(chip8.framebuffer())?;
+        }
+    }
+
+    if let Some(recorder) = recorder {
+        let (path, recorded) = (recorder.path().display().to_string(), recorder.frames());
+        recorder.finish()?;
+        println!("Saved {recorded} frame(s) to {path}.");
+    }
+
+    for (frame, path) in &screenshots {
+        if *frame > frames {
+            eprintln!("Stopped before frame {frame}, {path} was not written.");
+        }
+    }
+
+    if (until_pc.is_some() || until_opcode.is_some()) && !reached {
+        println!("FAIL: the stop condition was not reached in {frames} frame(s).");
+        return Ok(false);
+    }
+
+    println!("Stopped after {frames} frame(s) at {:#06X}.", chip8.pc());
+
+    let screen = screen_text(chip8.framebuffer());
+    let hash = format!("{:016x}", screen_hash(chip8.framebuffer()));
+
+    if let (true, Some(golden_path)) = (args.update, &args.golden_path) {
+        fs::write(golden_path, &screen).map_err(|e| format!("{golden_path}: {e}"))?;
+        println!("Wrote the screen to {golden_path}.");
+        return Ok(true);
+    }
+
+    let mut passed = true;
+
+    if let Some(golden_path) = &args.golden_path {
+        let golden = fs::read_to_string(golden_path).map_err(|e| format!("{golden_path}: {e}"))?;
+
+        if let Some(diff) = screen_diff(&golden, &screen) {
+            println!("FAIL: the screen does not match {golden_path}, + is an extra pixel and - a missing one:");
+            print!("{diff}");
+            passed = false;
+        }
+    }
+
+    if let Some(expected_hash) = &args.hash {
+        if !expected_hash.eq_ignore_ascii_case(&hash) {
+            println!("FAIL: the screen hash is {hash} instead of {expected_hash}, the screen is:");
+            print!("{screen}");
+            passed = false;
+        }
+    }
+
+    if args.golden_path.is_none() && args.hash.is_none() {
+        print!("{screen}");
+        println!("Screen hash: {hash}");
+    } else if passed {
+        println!("PASS");
+    }
+
+    Ok(passed)
+}
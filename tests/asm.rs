@@ -0,0 +1,188 @@
+// Assembly of every kind of statement, and errors pointing at the file and line they come from.
+
+use std::{fs, path::PathBuf};
+
+use chip_8_rs::{assemble, assemble_file};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("chip-8-rs-{}-{name}", std::process::id()));
+    fs::create_dir_all(&path).unwrap();
+
+    path
+}
+
+fn error(source: &str) -> String {
+    assemble(source).unwrap_err()
+}
+
+#[test]
+fn labels_and_constants_are_resolved() {
+    let rom = assemble(
+        "
+SPEED equ 3
+STEP equ SPEED + 1
+start:
+    ld v0, SPEED
+loop: add v0, STEP
+    ld i, sprite + 1
+    jp loop
+    call start
+sprite:
+    db 0xFF, 0x81
+",
+    )
+    .unwrap();
+
+    assert_eq!(
+        rom,
+        [0x60, 0x03, 0x70, 0x04, 0xA2, 0x0B, 0x12, 0x02, 0x22, 0x00, 0xFF, 0x81]
+    );
+}
+
+#[test]
+fn data_is_stored_as_is() {
+    let rom = assemble("db 1, 0x2, #FF, $10, 0b101, -1\ndw 0x1234, end\nend:").unwrap();
+
+    assert_eq!(rom, [1, 2, 0xFF, 0x10, 5, 0xFF, 0x12, 0x34, 0x02, 0x0A]);
+}
+
+#[test]
+fn includes_are_relative_to_the_file_including_them() {
+    let directory = temp_dir("includes");
+    fs::create_dir_all(directory.join("sprites")).unwrap();
+
+    fs::write(
+        directory.join("main.asm"),
+        "cls\ninclude \"sprites/all.asm\"\njp ship",
+    )
+    .unwrap();
+    fs::write(directory.join("sprites/all.asm"), "include \"ship.asm\"").unwrap();
+    fs::write(directory.join("sprites/ship.asm"), "ship: db 0x3C").unwrap();
+    fs::write(
+        directory.join("missing.asm"),
+        "cls\ninclude \"nowhere.asm\"",
+    )
+    .unwrap();
+    fs::write(directory.join("loop.asm"), "include \"loop.asm\"").unwrap();
+
+    let rom = assemble_file(directory.join("main.asm"));
+    let missing = assemble_file(directory.join("missing.asm"));
+    let looping = assemble_file(directory.join("loop.asm"));
+
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(rom.unwrap(), [0x00, 0xE0, 0x3C, 0x12, 0x02]);
+
+    let missing = missing.unwrap_err();
+    assert!(
+        missing.starts_with(&format!(
+            "{}:2: cannot include",
+            directory.join("missing.asm").display()
+        )),
+        "{missing}"
+    );
+
+    assert!(looping
+        .unwrap_err()
+        .contains("includes are nested too deeply"));
+}
+
+#[test]
+fn operands_out_of_range_are_rejected() {
+    for (source, message) in [
+        (
+            "cls\nld v0, 256",
+            "<source>:2: '256' does not fit in a byte",
+        ),
+        ("add v1, -129", "<source>:1: '-129' does not fit in a byte"),
+        (
+            "jp 0x1000",
+            "<source>:1: '0x1000' is out of range (0 to 0xFFF)",
+        ),
+        (
+            "drw v0, v1, 16",
+            "<source>:1: '16' is out of range (0 to 0xF)",
+        ),
+        (
+            "ld i, long 0x10000",
+            "<source>:1: '0x10000' is out of range (0 to 0xFFFF)",
+        ),
+        (
+            "db 9223372036854775807 + 1",
+            "<source>:1: '9223372036854775807 + 1' is out of range",
+        ),
+        (
+            "ld v0, -9223372036854775807 - 2",
+            "<source>:1: '-9223372036854775807 - 2' is out of range",
+        ),
+        (
+            "BIG equ 9223372036854775807\nld v0, BIG + BIG",
+            "<source>:2: 'BIG + BIG' is out of range",
+        ),
+        ("ld vg, 1", "<source>:1: invalid operands for LD"),
+        ("or v0, 1", "<source>:1: expected a register, found '1'"),
+        ("jump 0x200", "<source>:1: unknown instruction 'JUMP'"),
+    ] {
+        assert_eq!(error(source), message);
+    }
+}
+
+#[test]
+fn labels_must_be_defined_once() {
+    assert_eq!(
+        error("loop:\n    cls\nloop:\n    jp loop"),
+        "<source>:3: 'loop' is already defined"
+    );
+    assert_eq!(
+        error("SIZE equ 1\nSIZE: cls"),
+        "<source>:2: 'SIZE' is already defined"
+    );
+    assert_eq!(
+        error("cls\n    jp nowhere ; comment"),
+        "<source>:2: undefined label or constant 'nowhere'"
+    );
+
+    let circular = error("A equ B\nB equ A\nld v0, A");
+    assert!(circular.starts_with("<source>:3: "), "{circular}");
+    assert!(
+        circular.ends_with("is defined in terms of itself"),
+        "{circular}"
+    );
+
+    let directory = temp_dir("errors");
+    let path = directory.join("game.asm");
+    fs::write(&path, "cls\n\njp nowhere").unwrap();
+
+    let result = assemble_file(&path);
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(
+        result.unwrap_err(),
+        format!(
+            "{}:3: undefined label or constant 'nowhere'",
+            path.display()
+        )
+    );
+}
+
+#[test]
+fn programs_must_fit_in_memory() {
+    // 0x200 to 0xFFFF is filled exactly, a label after it would be past the end
+    let filled = "db 0\n".repeat(0x10000 - 0x200);
+
+    assert_eq!(assemble(&filled).unwrap().len(), 0x10000 - 0x200);
+    assert_eq!(
+        error(&format!("{filled}end:")),
+        format!(
+            "<source>:{}: 'end' is past the end of memory",
+            0x10000 - 0x200 + 1
+        )
+    );
+    assert_eq!(
+        error(&format!("{filled}db 0")),
+        format!(
+            "<source>:{}: the program does not fit in memory",
+            0x10000 - 0x200 + 1
+        )
+    );
+}
@@ -0,0 +1,140 @@
+// Runs programs with the interpreter and with translated blocks under every quirks preset and
+// timing, and checks that both leave the machine in the same state after every frame.
+
+use chip_8_rs::{assemble, assemble_file, Backend, Chip8, Quirks, QuirksPreset, Timing, Variant};
+
+const FRAMES: usize = 120;
+
+// Overwrites the next instruction of its loop, in the middle of the block it runs in
+const SELF_MODIFYING: &str = "
+loop:
+    ld i, patch
+    add v3, 1
+    ld v0, 0x72
+    ld v1, v3
+    ld [i], v1
+patch:
+    add v2, 1
+    jp loop
+";
+
+fn programs() -> Vec<(String, Variant, Vec<u8>)> {
+    let mut programs = vec![(
+        "self_modifying".to_string(),
+        Variant::Chip8,
+        assemble(SELF_MODIFYING).unwrap(),
+    )];
+
+    for name in [
+        "danm8ku",
+        "delay_timer_test",
+        "flightrunner",
+        "heart_monitor",
+        "test_opcode",
+    ] {
+        let variant = if name == "danm8ku" {
+            Variant::XoChip
+        } else {
+            Variant::Chip8
+        };
+        let rom = std::fs::read(format!("programs/{name}.ch8")).unwrap();
+        programs.push((name.to_string(), variant, rom));
+    }
+
+    for name in ["flags", "flags_edge", "keypad", "quirks"] {
+        let rom = assemble_file(format!("tests/roms/{name}.asm")).unwrap();
+        programs.push((name.to_string(), Variant::Chip8, rom));
+    }
+
+    programs
+}
+
+fn load(rom: &[u8], variant: Variant, preset: QuirksPreset, timing: Timing) -> Chip8 {
+    let mut chip8 = Chip8::with_variant(variant);
+    chip8.set_quirks(Quirks::from_preset(preset));
+    chip8.set_timing(timing);
+    chip8.set_seed(0);
+    chip8.load_rom(rom).unwrap();
+
+    chip8
+}
+
+#[test]
+fn blocks_match_the_interpreter() {
+    for (name, variant, rom) in programs() {
+        for preset in QuirksPreset::ALL {
+            for timing in Timing::ALL {
+                let mut interpreter = load(&rom, variant, preset, timing);
+                let mut blocks = load(&rom, variant, preset, timing);
+                blocks.set_backend(Backend::Blocks);
+
+                for frame in 0..FRAMES {
+                    // Keys change now and then so the keypad instructions take both branches
+                    let key = (frame / 8 % 16) as u8;
+                    let pressed = frame % 16 < 8;
+
+                    for chip8 in [&mut interpreter, &mut blocks] {
+                        chip8.set_key(key, pressed);
+                    }
+
+                    let expected = interpreter.run_frame().map_err(|e| e.to_string());
+                    let result = blocks.run_frame().map_err(|e| e.to_string());
+                    let context = format!("{name} with {preset} quirks and {timing} timing");
+
+                    assert_eq!(result, expected, "{context}, frame {frame}");
+                    assert!(
+                        blocks.save_state() == interpreter.save_state(),
+                        "{context}, states differ after frame {frame}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn frames_run_until_a_break_use_the_backend() {
+    for (name, variant, rom) in programs() {
+        let preset = QuirksPreset::CosmacVip;
+        let mut interpreter = load(&rom, variant, preset, Timing::default());
+        let mut blocks = load(&rom, variant, preset, Timing::default());
+        blocks.set_backend(Backend::Blocks);
+
+        // Breaks in the middle of blocks now and then, the frame resumes from there
+        let mut breaks = 0;
+
+        for frame in 0..FRAMES {
+            let mut instructions = [0; 2];
+            let mut results = vec![];
+
+            for (chip8, count) in [&mut interpreter, &mut blocks]
+                .into_iter()
+                .zip(&mut instructions)
+            {
+                let result = chip8.run_frame_until(|_| {
+                    *count += 1;
+                    *count == 7 && frame % 3 == 0
+                });
+                results.push(result.map_err(|e| e.to_string()));
+            }
+
+            if results[0] == Ok(true) {
+                breaks += 1;
+            }
+
+            assert_eq!(results[1], results[0], "{name}, frame {frame}");
+            assert_eq!(instructions[1], instructions[0], "{name}, frame {frame}");
+            assert!(
+                blocks.save_state() == interpreter.save_state(),
+                "{name}, states differ after frame {frame}"
+            );
+        }
+
+        assert!(breaks > 0 || interpreter.is_halted(), "{name} never broke");
+        assert_eq!(interpreter.translated_blocks(), 0);
+        assert!(
+            blocks.translated_blocks() > 0,
+            "{name} ran without translating blocks"
+        );
+    }
+}
@@ -0,0 +1,51 @@
+// Programs overwriting their own code run the same with and without the instruction cache.
+
+use chip_8_rs::{assemble, Chip8};
+
+// Runs the instruction at `patch` once, then overwrites it and jumps back to it
+const SELF_MODIFYING: &str = "
+    ld v3, 0
+patch:
+    ld v4, 1
+    se v3, 0
+    jp done
+    ld v3, 1
+    ld v0, 0x64
+    ld v1, 0x09
+    ld i, patch
+    ld [i], v1
+    jp patch
+done:
+    jp done
+";
+
+#[test]
+fn overwritten_instructions_are_decoded_again() {
+    for cache in [false, true] {
+        let mut chip8 = Chip8::new();
+        chip8.set_instruction_cache(cache);
+        chip8.load_rom(&assemble(SELF_MODIFYING).unwrap()).unwrap();
+
+        for _ in 0..2 {
+            chip8.run_frame().unwrap();
+        }
+
+        assert_eq!(chip8.registers()[4], 0x09, "cache: {cache}");
+        assert_eq!(chip8.registers()[3], 1, "cache: {cache}");
+    }
+}
+
+#[test]
+fn loading_a_state_drops_the_cached_instructions() {
+    let mut chip8 = Chip8::new();
+    chip8.load_rom(&assemble(SELF_MODIFYING).unwrap()).unwrap();
+    let state = chip8.save_state();
+    chip8.run_frame().unwrap();
+
+    // The state has the instruction at `patch` as it was before being overwritten
+    chip8.load_state(&state).unwrap();
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+
+    assert_eq!(chip8.registers()[4], 0x01);
+}
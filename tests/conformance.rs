@@ -0,0 +1,151 @@
+// Runs test ROMs headlessly under every quirks preset and checks the screen they leave. The ROMs
+// in tests/roms draw their results as hex digits with tests/roms/report.asm, the expected screen
+// is built here from the expected digits. They are written for this repository and only stand in
+// for the community test suites: running the flags, quirks, keypad and display wait tests of
+// Timendus' suite here is still to do, their ROMs, licence and golden screens aren't vendored.
+
+use chip_8_rs::{
+    assemble_file, screen_diff, screen_text, Chip8, Quirks, QuirksPreset, FONT_DATA, HEIGHT, WIDTH,
+};
+
+const FRAMES: usize = 300;
+
+fn run(rom: &[u8], preset: QuirksPreset, frames: usize) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.set_quirks(Quirks::from_preset(preset));
+    chip8.set_seed(0);
+    chip8.load_rom(rom).unwrap();
+
+    run_frames(&mut chip8, frames);
+
+    chip8
+}
+
+fn run_frames(chip8: &mut Chip8, frames: usize) {
+    for _ in 0..frames {
+        chip8.run_frame().unwrap();
+    }
+}
+
+fn assemble_test_rom(name: &str) -> Vec<u8> {
+    assemble_file(format!("tests/roms/{name}.asm")).unwrap()
+}
+
+// Same layout as report.asm: 12 digits per row, 5 pixels apart, rows 6 pixels apart
+fn expected_screen(results: &[u8]) -> String {
+    let mut pixels = [[false; WIDTH]; HEIGHT];
+
+    for (index, result) in results.iter().enumerate() {
+        let (x, y) = ((index % 12) * 5, (index / 12) * 6);
+        let glyph = &FONT_DATA[*result as usize * 5..][..5];
+
+        for (row, byte) in glyph.iter().enumerate() {
+            for column in 0..8 {
+                if byte & (0x80 >> column) != 0 {
+                    pixels[y + row][x + column] ^= true;
+                }
+            }
+        }
+    }
+
+    pixels
+        .iter()
+        .map(|row| {
+            let mut line: String = row.iter().map(|on| if *on { '#' } else { '.' }).collect();
+            line.push('\n');
+            line
+        })
+        .collect()
+}
+
+fn assert_screen(chip8: &Chip8, expected: &str, description: &str) {
+    if let Some(diff) = screen_diff(expected, &screen_text(chip8.framebuffer())) {
+        panic!("{description}: the screen is not the expected one\n{diff}");
+    }
+}
+
+#[test]
+fn test_opcode_passes_with_every_preset() {
+    let rom = std::fs::read("programs/test_opcode.ch8").unwrap();
+    let expected = std::fs::read_to_string("tests/golden/test_opcode.txt").unwrap();
+
+    for preset in QuirksPreset::ALL {
+        let chip8 = run(&rom, preset, FRAMES);
+
+        assert_screen(&chip8, &expected, &format!("test_opcode with {preset}"));
+    }
+}
+
+#[test]
+fn flags_are_set_with_every_preset() {
+    let rom = assemble_test_rom("flags");
+    let expected = expected_screen(&[1, 2, 0, 0xB, 1, 5, 0, 1, 5, 0, 1, 1, 2, 1, 0, 1]);
+
+    for preset in QuirksPreset::ALL {
+        let chip8 = run(&rom, preset, FRAMES);
+
+        assert_screen(&chip8, &expected, &format!("flags with {preset}"));
+    }
+}
+
+#[test]
+fn flags_corner_cases_with_every_preset() {
+    let rom = assemble_test_rom("flags_edge");
+    let expected = expected_screen(&[0, 1, 1, 1, 1, 0]);
+
+    for preset in QuirksPreset::ALL {
+        let chip8 = run(&rom, preset, FRAMES);
+
+        assert_screen(
+            &chip8,
+            &expected,
+            &format!("flags corner cases with {preset}"),
+        );
+    }
+}
+
+#[test]
+fn quirks_match_each_preset() {
+    let rom = assemble_test_rom("quirks");
+
+    for preset in QuirksPreset::ALL {
+        let quirks = Quirks::from_preset(preset);
+
+        let expected = expected_screen(&[
+            if quirks.vf_reset { 0 } else { 1 },
+            if quirks.shift_uses_vy { 4 } else { 1 },
+            if quirks.load_store_increments_i { 7 } else { 3 },
+            if quirks.jump_uses_vx { 1 } else { 0 },
+            if quirks.clip_sprites { 0 } else { 1 },
+            if quirks.display_wait { 1 } else { 0 },
+        ]);
+
+        let chip8 = run(&rom, preset, FRAMES);
+
+        assert_screen(&chip8, &expected, &format!("quirks with {preset}"));
+    }
+}
+
+#[test]
+fn keypad_is_read_with_every_preset() {
+    let rom = assemble_test_rom("keypad");
+
+    for preset in QuirksPreset::ALL {
+        // FX0A keeps waiting while no key is pressed
+        let mut chip8 = run(&rom, preset, 10);
+        assert_screen(
+            &chip8,
+            &expected_screen(&[]),
+            &format!("keypad with {preset}"),
+        );
+
+        chip8.set_key(5, true);
+        run_frames(&mut chip8, FRAMES);
+
+        assert_screen(
+            &chip8,
+            &expected_screen(&[5, 0, 1, 1, 0]),
+            &format!("keypad with {preset}"),
+        );
+    }
+}
@@ -0,0 +1,143 @@
+// Disassembled listings tell code from data, label the targets defined in them and assemble back
+// to the same bytes.
+
+use chip_8_rs::{assemble, disassemble, disassemble_instruction, Disassembly, Variant};
+
+// The listing as assembly source, labels on their own line
+fn source(disassembly: &Disassembly) -> String {
+    let mut source = String::new();
+
+    for line in &disassembly.lines {
+        if let Some(label) = &line.label {
+            source.push_str(&format!("{label}:\n"));
+        }
+
+        source.push_str(&format!("    {}\n", line.text));
+    }
+
+    source
+}
+
+fn texts(disassembly: &Disassembly) -> Vec<(u16, Option<&str>, &str)> {
+    disassembly
+        .lines
+        .iter()
+        .map(|line| (line.address, line.label.as_deref(), line.text.as_str()))
+        .collect()
+}
+
+#[test]
+fn mnemonics_are_formatted() {
+    for (opcode, variant, text) in [
+        (0x632A, Variant::Chip8, "LD V3, #2A"),
+        (0xD015, Variant::Chip8, "DRW V0, V1, 5"),
+        (0x8126, Variant::Chip8, "SHR V1, V2"),
+        (0xA123, Variant::Chip8, "LD I, #123"),
+        (0xB300, Variant::Chip8, "JP V0, #300"),
+        (0xF333, Variant::Chip8, "LD B, V3"),
+        (0xF265, Variant::Chip8, "LD V2, [I]"),
+        (0x00C4, Variant::Chip8, "SYS #0C4"),
+        (0x00C4, Variant::SuperChip, "SCD 4"),
+        (0xF201, Variant::XoChip, "PLANE 2"),
+        (0x5001, Variant::Chip8, "DW #5001"),
+    ] {
+        assert_eq!(disassemble_instruction(opcode, variant), text);
+    }
+}
+
+#[test]
+fn code_is_told_from_data() {
+    // JP 0x204, then 2 bytes of data jumped over
+    let rom = [0x12, 0x04, 0xAB, 0xCD, 0x00, 0xE0, 0x12, 0x06];
+
+    assert_eq!(
+        texts(&disassemble(&rom, Variant::Chip8)),
+        [
+            (0x200, None, "JP L204"),
+            (0x202, None, "DB #AB"),
+            (0x203, None, "DB #CD"),
+            (0x204, Some("L204"), "CLS"),
+            (0x206, Some("L206"), "JP L206"),
+        ]
+    );
+}
+
+#[test]
+fn labels_go_on_odd_addresses() {
+    let rom = [0x12, 0x03, 0xFF, 0x00, 0xE0, 0x12, 0x03];
+
+    assert_eq!(
+        texts(&disassemble(&rom, Variant::Chip8)),
+        [
+            (0x200, None, "JP L203"),
+            (0x202, None, "DB #FF"),
+            (0x203, Some("L203"), "CLS"),
+            (0x205, None, "JP L203"),
+        ]
+    );
+}
+
+#[test]
+fn targets_without_a_line_stay_numbers() {
+    // Outside the ROM, and in the middle of F000 NNNN
+    let rom = [0x23, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x12, 0x04];
+
+    assert_eq!(
+        texts(&disassemble(&rom, Variant::XoChip)),
+        [
+            (0x200, None, "CALL #300"),
+            (0x202, None, "LD I, LONG #1234"),
+            (0x206, None, "JP #204"),
+        ]
+    );
+}
+
+#[test]
+fn listings_assemble_back_to_the_rom() {
+    let mut roms = vec![
+        (
+            vec![0x12, 0x04, 0xAB, 0xCD, 0x00, 0xE0, 0x12, 0x06],
+            Variant::Chip8,
+        ),
+        (
+            vec![0x12, 0x03, 0xFF, 0x00, 0xE0, 0x12, 0x03],
+            Variant::Chip8,
+        ),
+        (
+            vec![0x23, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x12, 0x04],
+            Variant::XoChip,
+        ),
+        (vec![0x60, 0x01, 0xF0, 0x00], Variant::XoChip),
+        (vec![0x00, 0xE0, 0x12], Variant::Chip8),
+    ];
+
+    for name in [
+        "danm8ku",
+        "delay_timer_test",
+        "flightrunner",
+        "heart_monitor",
+        "test_opcode",
+    ] {
+        let variant = if name == "danm8ku" {
+            Variant::XoChip
+        } else {
+            Variant::Chip8
+        };
+
+        roms.push((
+            std::fs::read(format!("programs/{name}.ch8")).unwrap(),
+            variant,
+        ));
+    }
+
+    for (rom, variant) in roms {
+        let source = source(&disassemble(&rom, variant));
+
+        let assembled = assemble(&source).unwrap_or_else(|e| panic!("{e} in\n{source}"));
+
+        assert!(
+            assembled == rom,
+            "the listing doesn't assemble back:\n{source}"
+        );
+    }
+}
@@ -0,0 +1,200 @@
+// Stack overflows and underflows under each fault policy, unknown opcodes in strict mode, the
+// PC running past the end of memory and the stack in fault reports.
+
+use chip_8_rs::{assemble, Backend, Chip8, Chip8Error, FaultPolicy, Variant};
+
+// Recurses forever, V0 counts the calls
+const RECURSION: &str = "
+recurse:
+    add v0, 1
+    call recurse
+    jp recurse
+";
+
+const RETURN_FROM_MAIN: &str = "
+    ret
+    ld v0, 1
+loop:
+    jp loop
+";
+
+fn load(source: &str, fault_policy: FaultPolicy) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.set_fault_policy(fault_policy);
+    chip8.load_rom(&assemble(source).unwrap()).unwrap();
+
+    chip8
+}
+
+fn step(chip8: &mut Chip8, count: usize) -> Result<(), Chip8Error> {
+    for _ in 0..count {
+        chip8.step()?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn stack_overflow_halts_with_a_report() {
+    let mut chip8 = load(RECURSION, FaultPolicy::Halt);
+
+    let error = step(&mut chip8, 100).unwrap_err();
+
+    match &error {
+        Chip8Error::StackOverflow { pc, opcode, stack } => {
+            assert_eq!(*pc, Some(0x202));
+            assert_eq!(*opcode, Some(0x2200));
+            assert_eq!(stack, &[0x204; 16]);
+        }
+        _ => panic!("expected a stack overflow, got {error}"),
+    }
+
+    assert!(error.to_string().contains("Stack: [0x0204"));
+    assert!(chip8.is_halted());
+    assert_eq!(chip8.pc(), 0x202);
+    assert_eq!(chip8.registers()[0], 17);
+}
+
+#[test]
+fn every_fault_reports_the_stack() {
+    // Writes past the end of memory 2 subroutines deep
+    let mut chip8 = load(
+        "
+    call outer
+outer:
+    call inner
+inner:
+    ld i, 0xFFF
+    ld [i], v1
+",
+        FaultPolicy::Halt,
+    );
+
+    let error = step(&mut chip8, 4).unwrap_err();
+
+    assert!(matches!(error, Chip8Error::MemoryOutOfBounds { .. }));
+    assert_eq!(error.stack(), Some(&[0x202, 0x204][..]));
+    assert!(
+        error.to_string().ends_with("\nStack: [0x0202, 0x0204]"),
+        "{error}"
+    );
+
+    let mut chip8 = load(RETURN_FROM_MAIN, FaultPolicy::Halt);
+    let error = chip8.step().unwrap_err();
+
+    assert_eq!(error.stack(), Some(&[][..]));
+    assert!(error.to_string().ends_with("\nStack: []"), "{error}");
+
+    // Faults outside of an instruction have no stack
+    let error = chip8.read_ram(0x1000, 1).unwrap_err();
+
+    assert_eq!(error.stack(), None);
+    assert!(!error.to_string().contains("Stack"), "{error}");
+}
+
+#[test]
+fn stack_overflow_is_skipped_or_wraps() {
+    // Once the stack is full every call falls through to the jump
+    let mut chip8 = load(RECURSION, FaultPolicy::Ignore);
+    step(&mut chip8, 32 + 3 * 10).unwrap();
+
+    assert_eq!(chip8.sp(), 16);
+    assert_eq!(chip8.registers()[0], 16 + 10);
+    assert_eq!(chip8.stack(), &[0x204; 16]);
+
+    let mut chip8 = load(RECURSION, FaultPolicy::Wrap);
+    step(&mut chip8, 100).unwrap();
+
+    assert_eq!(chip8.sp(), 50 % 16);
+    assert_eq!(chip8.registers()[0], 50);
+}
+
+#[test]
+fn stack_underflow_follows_the_fault_policy() {
+    let mut chip8 = load(RETURN_FROM_MAIN, FaultPolicy::Halt);
+    let error = chip8.step().unwrap_err();
+
+    assert!(matches!(
+        error,
+        Chip8Error::StackUnderflow {
+            pc: Some(0x200),
+            opcode: Some(0x00EE),
+            ..
+        }
+    ));
+
+    let mut chip8 = load(RETURN_FROM_MAIN, FaultPolicy::Ignore);
+    step(&mut chip8, 2).unwrap();
+
+    assert_eq!(chip8.registers()[0], 1);
+
+    // The return address comes from the other end of the stack, still zeroed here
+    let mut chip8 = load(RETURN_FROM_MAIN, FaultPolicy::Wrap);
+    chip8.step().unwrap();
+
+    assert_eq!((chip8.pc(), chip8.sp()), (0x000, 15));
+}
+
+#[test]
+fn strict_mode_rejects_unknown_opcodes_and_machine_code() {
+    for (opcode, message) in [(0x0123, "0NNN"), (0x5001, "Unknown instruction")] {
+        let mut chip8 = Chip8::new();
+        chip8.load_rom(&u16::to_be_bytes(opcode)).unwrap();
+        chip8.step().unwrap();
+
+        assert_eq!(chip8.pc(), 0x202);
+
+        let mut chip8 = Chip8::new();
+        chip8.set_strict(true);
+        chip8.load_rom(&u16::to_be_bytes(opcode)).unwrap();
+        let error = chip8.step().unwrap_err();
+
+        assert!(matches!(error, Chip8Error::InvalidOpcode { .. }));
+        assert_eq!(error.opcode(), Some(opcode));
+        assert!(error.to_string().contains(message));
+    }
+}
+
+#[test]
+fn pc_wraps_around_the_end_of_xochip_memory() {
+    // LD V0, 0x2A then SE V0, 0x2A skipping the last instruction, then F000 NNNN reading its
+    // address from the last 2 bytes
+    for (code, pc, v0, i) in [
+        (&[0x60, 0x2A][..], 0xFFFE, 0x2A, 0),
+        (&[0x30, 0x00, 0x60, 0x2A], 0xFFFC, 0, 0),
+        (&[0xF0, 0x00, 0x12, 0x34], 0xFFFC, 0, 0x1234),
+    ] {
+        for backend in Backend::ALL {
+            let mut chip8 = Chip8::with_variant(Variant::XoChip);
+            chip8.set_backend(backend);
+            chip8.write_ram(pc as usize, code).unwrap();
+            chip8.set_pc(pc);
+
+            assert!(chip8.run_frame_until(|chip8| chip8.pc() < 0x200).unwrap());
+            assert_eq!(chip8.pc(), 0x000, "{code:02X?} with {backend}");
+            assert_eq!(chip8.registers()[0], v0);
+            assert_eq!(chip8.i(), i);
+        }
+    }
+
+    // LD V0, K waiting for a key in the last 2 bytes stays there
+    for backend in Backend::ALL {
+        let mut chip8 = Chip8::with_variant(Variant::XoChip);
+        chip8.set_backend(backend);
+        chip8.write_ram(0xFFFE, &[0xF0, 0x0A]).unwrap();
+        chip8.set_pc(0xFFFE);
+
+        chip8.run_frame().unwrap();
+        assert_eq!(chip8.pc(), 0xFFFE, "LD V0, K with {backend}");
+    }
+
+    // The 4K variants fault instead
+    let mut chip8 = Chip8::with_variant(Variant::Chip8);
+    chip8.set_pc(0xFFE);
+    chip8.step().unwrap();
+
+    assert!(matches!(
+        chip8.step().unwrap_err(),
+        Chip8Error::MemoryOutOfBounds { .. }
+    ));
+}
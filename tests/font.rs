@@ -0,0 +1,38 @@
+// FX29 points I at the 5 byte glyph of the hex digit in VX, other FX2N opcodes don't.
+
+use chip_8_rs::{Chip8, FONT_DATA};
+
+// Loads `digit` in V3 then runs `opcode`
+fn run(digit: u8, opcode: [u8; 2]) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8
+        .load_rom(&[0x63, digit, opcode[0], opcode[1]])
+        .unwrap();
+
+    chip8.step().unwrap();
+    chip8.step().unwrap();
+
+    chip8
+}
+
+#[test]
+fn fx29_points_i_at_the_glyph_of_the_digit() {
+    for digit in 0..=0xF {
+        let chip8 = run(digit, [0xF3, 0x29]);
+        let glyph = chip8.read_ram(chip8.i() as usize, 5).unwrap();
+
+        assert_eq!(chip8.i(), digit as u16 * 5);
+        assert_eq!(
+            glyph,
+            &FONT_DATA[digit as usize * 5..][..5],
+            "digit {digit:X}"
+        );
+    }
+}
+
+#[test]
+fn fx2e_is_not_the_font_instruction() {
+    let chip8 = run(7, [0xF3, 0x2E]);
+
+    assert_eq!(chip8.i(), 0);
+}
@@ -0,0 +1,384 @@
+// Differential fuzzing: random ROMs run from random register states on the emulator and on a
+// reference model written as plainly as possible, under every quirks preset. Both have to agree
+// after every instruction. Only CHIP-8 instructions are covered, the ones of later variants run
+// as unknown instructions.
+
+use chip_8_rs::{Chip8, Quirks, QuirksPreset, Variant, HEIGHT, WIDTH};
+use proptest::prelude::*;
+
+const RAM_SIZE: usize = 4096;
+const ROM_START: usize = 0x200;
+const STEPS: usize = 64;
+
+// Everything an instruction can change, the framebuffer included
+struct Model {
+    v: [u8; 16],
+    i: u16,
+    pc: u16,
+    stack: Vec<u16>,
+    delay_timer: u8,
+    sound_timer: u8,
+    ram: Vec<u8>,
+    screen: Vec<[bool; WIDTH]>,
+}
+
+impl Model {
+    fn from_chip8(chip8: &Chip8) -> Model {
+        Model {
+            v: *chip8.registers(),
+            i: chip8.i(),
+            pc: chip8.pc(),
+            stack: chip8.stack()[..chip8.sp() as usize].to_vec(),
+            delay_timer: chip8.delay_timer(),
+            sound_timer: chip8.sound_timer(),
+            ram: chip8.read_ram(0, RAM_SIZE).unwrap().to_vec(),
+            screen: (0..HEIGHT)
+                .map(|y| std::array::from_fn(|x| chip8.framebuffer().pixel(x, y)))
+                .collect(),
+        }
+    }
+
+    // What differs from `expected`, RAM and screen by address and pixel
+    fn differences(&self, expected: &Model) -> Vec<String> {
+        let mut differences = vec![];
+
+        let fields = [
+            ("V", format!("{:?}", self.v), format!("{:?}", expected.v)),
+            (
+                "I",
+                format!("{:#05X}", self.i),
+                format!("{:#05X}", expected.i),
+            ),
+            (
+                "PC",
+                format!("{:#05X}", self.pc),
+                format!("{:#05X}", expected.pc),
+            ),
+            (
+                "stack",
+                format!("{:X?}", self.stack),
+                format!("{:X?}", expected.stack),
+            ),
+            (
+                "DT",
+                self.delay_timer.to_string(),
+                expected.delay_timer.to_string(),
+            ),
+            (
+                "ST",
+                self.sound_timer.to_string(),
+                expected.sound_timer.to_string(),
+            ),
+        ];
+
+        for (name, actual, expected) in fields {
+            if actual != expected {
+                differences.push(format!("{name} is {actual} instead of {expected}"));
+            }
+        }
+
+        for (address, (actual, expected)) in self.ram.iter().zip(&expected.ram).enumerate() {
+            if actual != expected {
+                differences.push(format!(
+                    "RAM[{address:#05X}] is {actual} instead of {expected}"
+                ));
+            }
+        }
+
+        for (y, (actual, expected)) in self.screen.iter().zip(&expected.screen).enumerate() {
+            for x in 0..WIDTH {
+                if actual[x] != expected[x] {
+                    differences.push(format!("pixel ({x}, {y}) is {}", actual[x]));
+                }
+            }
+        }
+
+        differences
+    }
+
+    fn load(&self, address: u16, length: usize) -> Result<&[u8], ()> {
+        self.ram
+            .get(address as usize..address as usize + length)
+            .ok_or(())
+    }
+
+    // Runs the instruction at PC, an error is any fault. CXKK takes `random` as its random byte.
+    fn step(&mut self, quirks: &Quirks, keys: &[bool; 16], random: u8) -> Result<(), ()> {
+        let bytes = self.load(self.pc, 2)?;
+        let opcode = u16::from_be_bytes([bytes[0], bytes[1]]);
+        self.pc += 2;
+
+        let x = (opcode >> 8 & 0xF) as usize;
+        let y = (opcode >> 4 & 0xF) as usize;
+        let n = opcode & 0xF;
+        let kk = opcode as u8;
+        let nnn = opcode & 0xFFF;
+
+        match opcode >> 12 {
+            0x0 if opcode == 0x00E0 => self.screen = vec![[false; WIDTH]; HEIGHT],
+            0x0 if opcode == 0x00EE => self.pc = self.stack.pop().ok_or(())?,
+            0x1 => self.pc = nnn,
+            0x2 if self.stack.len() == 16 => return Err(()),
+            0x2 => {
+                self.stack.push(self.pc);
+                self.pc = nnn;
+            }
+            0x3 if self.v[x] == kk => self.pc += 2,
+            0x4 if self.v[x] != kk => self.pc += 2,
+            0x5 if n == 0 && self.v[x] == self.v[y] => self.pc += 2,
+            0x6 => self.v[x] = kk,
+            0x7 => self.v[x] = self.v[x].wrapping_add(kk),
+            0x8 => self.arithmetic(quirks, x, y, n),
+            0x9 if n == 0 && self.v[x] != self.v[y] => self.pc += 2,
+            0xA => self.i = nnn,
+            0xB if quirks.jump_uses_vx => self.pc = nnn + self.v[x] as u16,
+            0xB => self.pc = nnn + self.v[0] as u16,
+            0xC => self.v[x] = random & kk,
+            0xD => self.draw(quirks, x, y, n as usize)?,
+            0xE if kk == 0x9E || kk == 0xA1 => {
+                let pressed = *keys.get(self.v[x] as usize).ok_or(())?;
+
+                if pressed == (kk == 0x9E) {
+                    self.pc += 2;
+                }
+            }
+            0xF => self.misc(quirks, keys, x, kk)?,
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn arithmetic(&mut self, quirks: &Quirks, x: usize, y: usize, n: u16) {
+        let (vx, vy) = (self.v[x], self.v[y]);
+        let shifted = if quirks.shift_uses_vy { vy } else { vx };
+
+        // The flag is written last, it wins when VF is the destination
+        let (result, flag) = match n {
+            0x0 => (vy, None),
+            0x1..=0x3 => {
+                let result = match n {
+                    0x1 => vx | vy,
+                    0x2 => vx & vy,
+                    _ => vx ^ vy,
+                };
+
+                (result, quirks.vf_reset.then_some(0))
+            }
+            0x4 => (
+                vx.wrapping_add(vy),
+                Some((vx as u16 + vy as u16 > 0xFF) as u8),
+            ),
+            0x5 => (vx.wrapping_sub(vy), Some((vx >= vy) as u8)),
+            0x6 => (shifted >> 1, Some(shifted & 1)),
+            0x7 => (vy.wrapping_sub(vx), Some((vy >= vx) as u8)),
+            0xE => (shifted << 1, Some(shifted >> 7)),
+            _ => return,
+        };
+
+        self.v[x] = result;
+
+        if let Some(flag) = flag {
+            self.v[0xF] = flag;
+        }
+    }
+
+    fn draw(&mut self, quirks: &Quirks, x: usize, y: usize, n: usize) -> Result<(), ()> {
+        let sprite = self.load(self.i, n)?.to_vec();
+        let (left, top) = (self.v[x] as usize % WIDTH, self.v[y] as usize % HEIGHT);
+        let mut collided = false;
+
+        for (row, byte) in sprite.iter().enumerate() {
+            for column in 0..8 {
+                let (x, y) = (left + column, top + row);
+
+                if quirks.clip_sprites && (x >= WIDTH || y >= HEIGHT) {
+                    continue;
+                }
+
+                if byte & (0x80 >> column) != 0 {
+                    let pixel = &mut self.screen[y % HEIGHT][x % WIDTH];
+                    collided |= *pixel;
+                    *pixel = !*pixel;
+                }
+            }
+        }
+
+        self.v[0xF] = collided as u8;
+
+        Ok(())
+    }
+
+    fn misc(&mut self, quirks: &Quirks, keys: &[bool; 16], x: usize, kk: u8) -> Result<(), ()> {
+        match kk {
+            0x07 => self.v[x] = self.delay_timer,
+            0x0A => match keys.iter().position(|pressed| *pressed) {
+                Some(key) => self.v[x] = key as u8,
+                None => self.pc -= 2,
+            },
+            0x15 => self.delay_timer = self.v[x],
+            0x18 => self.sound_timer = self.v[x],
+            0x1E => self.i = self.i.wrapping_add(self.v[x] as u16),
+            0x29 => self.i = self.v[x] as u16 * 5,
+            0x33 => {
+                self.load(self.i, 3)?;
+
+                let digits = [self.v[x] / 100, self.v[x] / 10 % 10, self.v[x] % 10];
+                let i = self.i as usize;
+                self.ram[i..i + 3].copy_from_slice(&digits);
+            }
+            0x55 | 0x65 => {
+                self.load(self.i, x + 1)?;
+
+                let i = self.i as usize;
+
+                if kk == 0x55 {
+                    self.ram[i..=i + x].copy_from_slice(&self.v[..=x]);
+                } else {
+                    self.v[..=x].copy_from_slice(&self.ram[i..=i + x]);
+                }
+
+                if quirks.load_store_increments_i {
+                    self.i = self.i.wrapping_add(x as u16 + 1);
+                }
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+}
+
+// Mostly valid CHIP-8 instructions with random operands, and some entirely random opcodes
+fn opcode() -> impl Strategy<Value = u16> {
+    let operands = any::<u16>().prop_map(|opcode| opcode & 0x0FFF);
+    let low_bytes = [
+        0x07, 0x0A, 0x15, 0x18, 0x1E, 0x29, 0x33, 0x55, 0x65, 0x9E, 0xA1,
+    ];
+
+    prop_oneof![
+        any::<u16>(),
+        Just(0x00E0),
+        Just(0x00EE),
+        (1..=0xDu16, operands).prop_map(|(high, operands)| high << 12 | operands),
+        (
+            any::<u16>(),
+            prop::sample::select(vec![0, 1, 2, 3, 4, 5, 6, 7, 0xE])
+        )
+            .prop_map(|(opcode, n)| 0x8000 | (opcode & 0x0FF0) | n),
+        (0..16u16, prop::sample::select(low_bytes.to_vec())).prop_map(|(x, low_byte)| if low_byte
+            >= 0x9E
+        {
+            0xE000
+        } else {
+            0xF000
+        } | x << 8
+            | low_byte),
+    ]
+}
+
+#[derive(Debug)]
+struct Case {
+    rom: Vec<u8>,
+    v: [u8; 16],
+    i: u16,
+    delay_timer: u8,
+    sound_timer: u8,
+    keys: [bool; 16],
+}
+
+fn case() -> impl Strategy<Value = Case> {
+    (
+        prop::collection::vec(opcode(), 1..48),
+        any::<[u8; 16]>(),
+        // I mostly points somewhere in RAM, and sometimes just below its end
+        prop_oneof![0..RAM_SIZE as u16, RAM_SIZE as u16 - 16..=RAM_SIZE as u16],
+        any::<u8>(),
+        any::<u8>(),
+        any::<[bool; 16]>(),
+    )
+        .prop_map(|(opcodes, v, i, delay_timer, sound_timer, keys)| Case {
+            rom: opcodes
+                .iter()
+                .flat_map(|opcode| opcode.to_be_bytes())
+                .collect(),
+            v,
+            i,
+            delay_timer,
+            sound_timer,
+            keys,
+        })
+}
+
+fn load(case: &Case, preset: QuirksPreset) -> Chip8 {
+    let mut chip8 = Chip8::with_variant(Variant::Chip8);
+    chip8.set_quirks(Quirks::from_preset(preset));
+    chip8.load_rom(&case.rom).unwrap();
+
+    for (index, value) in case.v.iter().enumerate() {
+        chip8.set_register(index, *value);
+    }
+
+    chip8.set_i(case.i);
+    chip8.set_delay_timer(case.delay_timer);
+    chip8.set_sound_timer(case.sound_timer);
+
+    for (key, pressed) in case.keys.iter().enumerate() {
+        chip8.set_key(key as u8, *pressed);
+    }
+
+    chip8
+}
+
+proptest! {
+    // Failures are printed shrunk, they aren't saved next to the tests. PROPTEST_CASES sets how
+    // many cases run, 256 by default
+    #![proptest_config(ProptestConfig {
+        failure_persistence: None,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn emulator_matches_the_reference_model(case in case()) {
+        for preset in QuirksPreset::ALL {
+            let quirks = Quirks::from_preset(preset);
+            let mut chip8 = load(&case, preset);
+            let mut model = Model::from_chip8(&chip8);
+            prop_assert_eq!(model.pc as usize, ROM_START);
+
+            for step in 0..STEPS {
+                let opcode = chip8.opcode_at(chip8.pc());
+                let result = chip8.step();
+
+                // The random byte of CXKK is the one the emulator kept, masked again
+                let random = chip8.registers()[opcode.unwrap_or(0) as usize >> 8 & 0xF];
+                let expected = model.step(&quirks, &case.keys, random);
+
+                prop_assert_eq!(
+                    result.is_ok(),
+                    expected.is_ok(),
+                    "{} quirks, step {}, opcode {:04X?}: {:?}",
+                    preset,
+                    step,
+                    opcode,
+                    result
+                );
+
+                if result.is_err() {
+                    break;
+                }
+
+                let differences = Model::from_chip8(&chip8).differences(&model);
+
+                prop_assert!(
+                    differences.is_empty(),
+                    "{} quirks, step {}, opcode {:04X?}: {}",
+                    preset,
+                    step,
+                    opcode,
+                    differences.join(", ")
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Snapshots come back out of the history exactly as they went in, newest first, whatever changed
+// between them.
+
+use chip_8_rs::History;
+
+// Pushes every snapshot then pops them all back
+fn round_trip(snapshots: &[Vec<u8>]) {
+    let mut history = History::new(snapshots.len());
+
+    for snapshot in snapshots {
+        history.push(snapshot.clone());
+    }
+
+    assert_eq!(history.len(), snapshots.len());
+
+    for (index, snapshot) in snapshots.iter().enumerate().rev() {
+        assert_eq!(
+            history.latest(),
+            Some(snapshot.as_slice()),
+            "snapshot {index}"
+        );
+        assert_eq!(history.pop().as_ref(), Some(snapshot), "snapshot {index}");
+    }
+
+    assert!(history.is_empty());
+    assert_eq!(history.pop(), None);
+}
+
+#[test]
+fn unchanged_and_empty_snapshots_come_back() {
+    round_trip(&[vec![7; 100], vec![7; 100], vec![7; 100]]);
+    round_trip(&[vec![], vec![], vec![1]]);
+    round_trip(&[vec![1, 2, 3], vec![], vec![1, 2, 3]]);
+}
+
+#[test]
+fn scattered_changes_come_back() {
+    let first: Vec<u8> = (0..4096).map(|i| (i * 7) as u8).collect();
+
+    let mut second = first.clone();
+    second[0] ^= 1;
+    second[1000] = 0;
+    second[4095] = 0xFF;
+
+    let mut third = second.clone();
+    third[2000..2010].fill(0xAA);
+
+    round_trip(&[first, second, third]);
+}
+
+#[test]
+fn runs_longer_than_a_delta_length_come_back() {
+    // Runs of unchanged and changed bytes longer than a u16
+    let length = 200_000;
+    let first = vec![0; length];
+
+    let mut second = first.clone();
+    second[100_000..].fill(1);
+
+    let third: Vec<u8> = (0..length).map(|i| (i % 251) as u8 | 1).collect();
+
+    round_trip(&[first, second, third]);
+}
+
+#[test]
+fn snapshots_of_other_sizes_come_back() {
+    round_trip(&[vec![1; 10], vec![2; 20], vec![3; 5], vec![1; 10]]);
+    round_trip(&[vec![1, 0, 0, 0], vec![1]]);
+}
+
+#[test]
+fn the_oldest_snapshots_are_dropped() {
+    let mut history = History::new(3);
+
+    for value in 0..5 {
+        history.push(vec![value; 4]);
+    }
+
+    assert_eq!(history.len(), 3);
+
+    for value in [4, 3, 2] {
+        assert_eq!(history.pop(), Some(vec![value; 4]));
+    }
+
+    assert_eq!(history.pop(), None);
+
+    history.push(vec![9]);
+    history.clear();
+    assert!(history.is_empty());
+
+    let mut nothing = History::new(0);
+    nothing.push(vec![1]);
+    assert_eq!(nothing.len(), 0);
+    assert_eq!(nothing.latest(), None);
+}
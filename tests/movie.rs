@@ -0,0 +1,207 @@
+// Movies survive being written and read back, and replaying one gives the screen the recording
+// ended on.
+
+use std::fs;
+
+use chip_8_rs::{
+    assemble,
+    devices::{Input, InputEvent},
+    frontends::Headless,
+    screen_hash, Chip8, Chip8Error, Emulator, FaultPolicy, Movie, MovieInput, Quirks, QuirksPreset,
+    Timing, Variant,
+};
+
+const ROM_PATH: &str = "programs/flightrunner.ch8";
+
+// Plays a script of events, one list per pass of the emulator loop, then quits
+struct ScriptedInput {
+    script: Vec<Vec<InputEvent>>,
+    pass: usize,
+}
+
+impl Input for ScriptedInput {
+    fn poll_event(&mut self) -> Option<InputEvent> {
+        let Some(events) = self.script.get_mut(self.pass) else {
+            return Some(InputEvent::Quit);
+        };
+
+        if events.is_empty() {
+            self.pass += 1;
+            return None;
+        }
+
+        Some(events.remove(0))
+    }
+}
+
+// Does what the emulator does when it replays a movie
+fn replay(movie: &Movie, rom: &[u8]) -> Chip8 {
+    let mut chip8 = Chip8::with_variant(movie.variant);
+    chip8.load_rom(rom).unwrap();
+    chip8.set_quirks(movie.quirks);
+    chip8.set_seed(movie.seed);
+    chip8.set_clock_rate(movie.clock_rate);
+    chip8.set_timing(movie.timing);
+    chip8.set_fault_policy(movie.fault_policy);
+    chip8.set_strict(movie.strict);
+
+    let mut events = movie.events.iter().peekable();
+
+    for frame in 0..movie.length {
+        while let Some(event) = events.next_if(|event| event.frame <= frame) {
+            match event.input {
+                MovieInput::KeyDown(key) => chip8.set_key(key, true),
+                MovieInput::KeyUp(key) => chip8.set_key(key, false),
+                MovieInput::ClockRate(clock_rate) => chip8.set_clock_rate(clock_rate),
+            }
+        }
+
+        chip8.run_frame().unwrap();
+    }
+
+    chip8
+}
+
+#[test]
+fn movies_are_written_and_read_back() {
+    let mut chip8 = Chip8::with_variant(Variant::SuperChip);
+    chip8.set_quirks(Quirks::from_preset(QuirksPreset::Chip48));
+    chip8.set_timing(Timing::CosmacVip);
+    chip8.set_clock_rate(1234);
+    chip8.set_fault_policy(FaultPolicy::Wrap);
+    chip8.set_strict(true);
+    chip8.load_rom(&[0x12, 0x00]).unwrap();
+
+    let mut movie = Movie::new(&chip8, 42);
+    movie.record(0, MovieInput::KeyDown(0xA));
+    movie.record(12, MovieInput::KeyUp(0xA));
+    movie.record(12, MovieInput::ClockRate(3060));
+    movie.length = 600;
+
+    let text = movie.to_string();
+
+    assert!(text.starts_with("chip-8-rs movie 1\n"));
+    assert!(text.contains("\nfaults wrap\nstrict true\n"));
+    assert!(text.contains("\n12 up A\n12 clock 3060\nend 600\n"));
+    assert_eq!(text.parse::<Movie>(), Ok(movie));
+}
+
+#[test]
+fn broken_movies_are_refused() {
+    let movie =
+        "chip-8-rs movie 1\nrom 00000000000000ff\nvariant chip8\nquirks\nseed 1\nclock 600\ntiming instructions\nfaults halt\nstrict false\n";
+
+    assert_eq!(
+        format!("{movie}end 10").parse::<Movie>().unwrap().rom_hash,
+        0xFF
+    );
+    assert_eq!(movie.parse::<Movie>().unwrap_err(), "missing 'end' line");
+    assert_eq!(
+        format!("{movie}3 down G\nend 10")
+            .parse::<Movie>()
+            .unwrap_err(),
+        "line 10: invalid key 'G'"
+    );
+    assert_eq!(
+        format!("{movie}quirks fast\nend 10")
+            .parse::<Movie>()
+            .unwrap_err(),
+        "line 10: unknown quirk 'fast'"
+    );
+    assert_eq!(
+        format!("{}end 10", movie.replace("timing instructions\n", ""))
+            .parse::<Movie>()
+            .unwrap_err(),
+        "missing 'timing' line"
+    );
+    assert_eq!(
+        format!("{}end 10", movie.replace("strict false\n", ""))
+            .parse::<Movie>()
+            .unwrap_err(),
+        "missing 'strict' line"
+    );
+    assert_eq!(
+        format!("{movie}strict maybe\nend 10")
+            .parse::<Movie>()
+            .unwrap_err(),
+        "line 10: invalid strict mode 'maybe'"
+    );
+    assert!("chip-8-rs movie 2\n".parse::<Movie>().is_err());
+}
+
+#[test]
+fn replaying_a_recording_ends_on_the_same_screen() {
+    let path = std::env::temp_dir().join(format!("chip-8-rs-{}.movie", std::process::id()));
+
+    let mut script: Vec<Vec<InputEvent>> = (0..60).map(|_| vec![]).collect();
+    script[5].push(InputEvent::KeyDown(7));
+    script[15].push(InputEvent::KeyUp(7));
+    script[20].push(InputEvent::KeyDown(9));
+    script[25].push(InputEvent::SpeedUp);
+    script[35].push(InputEvent::KeyUp(9));
+
+    let input = ScriptedInput { script, pass: 0 };
+
+    let mut emulator = Emulator::new(Chip8::new(), Headless, Headless, input);
+    emulator.record_movie(&path, 1234);
+    emulator.run(ROM_PATH).unwrap();
+
+    let recorded = screen_hash(emulator.chip8().framebuffer());
+    let state = emulator.chip8().save_state();
+
+    let movie: Movie = fs::read_to_string(&path).unwrap().parse().unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(movie.seed, 1234);
+    assert_eq!(movie.events.len(), 5);
+    assert!(movie.length > 0);
+
+    let replayed = replay(&movie, &fs::read(ROM_PATH).unwrap());
+
+    assert_eq!(screen_hash(replayed.framebuffer()), recorded);
+    assert!(replayed.save_state() == state);
+}
+
+#[test]
+fn replaying_uses_the_recorded_fault_policy_and_strict_mode() {
+    // Calls itself forever, which only keeps running when the stack wraps
+    let recursion = assemble("start:\n    call start\n").unwrap();
+    // A machine code call, which only faults in strict mode
+    let sys = assemble("    sys 0x123\nloop:\n    jp loop\n").unwrap();
+
+    for (rom, fault_policy, strict) in [
+        (recursion, FaultPolicy::Wrap, false),
+        (sys, FaultPolicy::Halt, true),
+    ] {
+        let path =
+            std::env::temp_dir().join(format!("chip-8-rs-{}-replay.ch8", std::process::id()));
+        fs::write(&path, &rom).unwrap();
+
+        let mut chip8 = Chip8::new();
+        chip8.set_fault_policy(fault_policy);
+        chip8.set_strict(strict);
+        chip8.load_rom(&rom).unwrap();
+
+        let mut movie = Movie::new(&chip8, 1);
+        movie.length = 5;
+
+        // The emulator starts with the defaults, the movie has to switch to the recorded ones
+        let input = ScriptedInput {
+            script: (0..5).map(|_| vec![]).collect(),
+            pass: 0,
+        };
+        let mut emulator = Emulator::new(Chip8::new(), Headless, Headless, input);
+        emulator.replay_movie(movie);
+        let result = emulator.run(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(emulator.chip8().fault_policy(), fault_policy);
+        assert_eq!(emulator.chip8().is_strict(), strict);
+
+        if strict {
+            assert!(matches!(result, Err(Chip8Error::InvalidOpcode { .. })));
+        } else {
+            assert!(result.is_ok());
+        }
+    }
+}
@@ -0,0 +1,35 @@
+// CXKK follows the seed: the same seed gives the same numbers, another seed different ones.
+
+use chip_8_rs::{assemble, Chip8};
+
+// The registers after filling all of them with CXFF
+fn random_registers(seed: u64) -> [u8; 16] {
+    let source: String = (0..16).map(|x| format!("    rnd v{x:x}, 255\n")).collect();
+
+    let mut chip8 = Chip8::new();
+    chip8.set_seed(seed);
+    chip8.set_instructions_per_frame(16).unwrap();
+    chip8
+        .load_rom(&assemble(&format!("{source}end:\n    jp end\n")).unwrap())
+        .unwrap();
+    chip8.run_frame().unwrap();
+
+    *chip8.registers()
+}
+
+#[test]
+fn the_same_seed_gives_the_same_numbers() {
+    for seed in [0, 1, 1234, u64::MAX] {
+        assert_eq!(
+            random_registers(seed),
+            random_registers(seed),
+            "seed {seed}"
+        );
+    }
+}
+
+#[test]
+fn different_seeds_give_different_numbers() {
+    assert_ne!(random_registers(1), random_registers(2));
+    assert_ne!(random_registers(1234), random_registers(1235));
+}
@@ -0,0 +1,136 @@
+// Screen recordings hold one image per change of the screen, timed on the 60Hz timeline.
+
+use std::{fs, path::PathBuf};
+
+use chip_8_rs::{Framebuffer, Palette, ScreenRecorder, HIRES_WIDTH, MAX_SCREENSHOT_SCALE};
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("chip-8-rs-{}-{name}", std::process::id()))
+}
+
+// Delay and palette indices of a frame
+type GifFrame = (u16, Vec<u8>);
+
+// Size and frames of a GIF
+fn decode_gif(path: &PathBuf) -> ((u16, u16), Vec<GifFrame>) {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::Indexed);
+    let mut decoder = options.read_info(fs::File::open(path).unwrap()).unwrap();
+
+    let size = (decoder.width(), decoder.height());
+
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push((frame.delay, frame.buffer.to_vec()));
+    }
+
+    fs::remove_file(path).unwrap();
+
+    (size, frames)
+}
+
+#[test]
+fn gif_frames_are_merged_until_the_screen_changes() {
+    let path = temp_path("recording.gif");
+    let mut recorder = ScreenRecorder::new(&path, Palette::default(), 2);
+
+    let mut framebuffer = Framebuffer::new();
+
+    for _ in 0..3 {
+        recorder.add_frame(&framebuffer).unwrap();
+    }
+
+    framebuffer.set_pixel(0, 0, true);
+
+    for _ in 0..3 {
+        recorder.add_frame(&framebuffer).unwrap();
+    }
+
+    assert_eq!(recorder.frames(), 6);
+    recorder.finish().unwrap();
+
+    let (size, frames) = decode_gif(&path);
+
+    // Low resolution pixels take 2 by 2 high resolution pixels, scaled by 2
+    assert_eq!(size, (256, 128));
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].0, 5);
+    assert_eq!(frames[1].0, 5);
+    assert!(frames[0].1.iter().all(|color| *color == 0));
+    assert_eq!(&frames[1].1[..5], [1, 1, 1, 1, 0]);
+    assert_eq!(frames[1].1[256 * 3], 1);
+    assert_eq!(frames[1].1[256 * 4], 0);
+}
+
+#[test]
+fn switching_to_high_resolution_keeps_every_pixel() {
+    let path = temp_path("hires.gif");
+    let mut recorder = ScreenRecorder::new(&path, Palette::default(), 1);
+
+    let mut framebuffer = Framebuffer::new();
+    framebuffer.set_pixel(1, 0, true);
+
+    for _ in 0..3 {
+        recorder.add_frame(&framebuffer).unwrap();
+    }
+
+    framebuffer.set_hires(true);
+    framebuffer.set_pixel(127, 63, true);
+    framebuffer.set_pixel(126, 63, false);
+    recorder.add_frame(&framebuffer).unwrap();
+    recorder.add_frame(&framebuffer).unwrap();
+
+    recorder.finish().unwrap();
+
+    let (size, frames) = decode_gif(&path);
+
+    assert_eq!(size, (128, 64));
+    assert_eq!(frames.len(), 2);
+    assert_eq!(&frames[0].1[..5], [0, 0, 1, 1, 0]);
+    assert_eq!(&frames[0].1[128..133], [0, 0, 1, 1, 0]);
+    assert_eq!(frames[1].1[128 * 64 - 1], 1);
+    assert_eq!(frames[1].1[128 * 64 - 2], 0);
+}
+
+#[test]
+fn frame_directories_hold_one_png_per_frame() {
+    let path = temp_path("frames");
+    let mut recorder = ScreenRecorder::new(&path, Palette::default(), 1);
+
+    let framebuffer = Framebuffer::new();
+
+    for _ in 0..3 {
+        recorder.add_frame(&framebuffer).unwrap();
+    }
+
+    recorder.finish().unwrap();
+
+    let mut names: Vec<String> = fs::read_dir(&path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+
+    fs::remove_dir_all(&path).unwrap();
+
+    assert_eq!(names, ["000001.png", "000002.png", "000003.png"]);
+}
+
+#[test]
+fn gifs_too_large_are_refused() {
+    // The largest scale the command line takes fits
+    assert!(HIRES_WIDTH * MAX_SCREENSHOT_SCALE <= u16::MAX as usize);
+
+    // 128 pixels at scale 600 are wider than the 65535 a GIF can hold
+    let path = temp_path("too-large.gif");
+    let mut recorder = ScreenRecorder::new(&path, Palette::default(), 600);
+
+    let error = recorder.add_frame(&Framebuffer::new()).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("76800x38400 is too large for a GIF"),
+        "{error}"
+    );
+    assert!(!path.exists());
+}
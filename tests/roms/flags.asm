@@ -0,0 +1,87 @@
+; VF after arithmetic, shifts and sprite collisions. Shifts use the same register as VX and VY so
+; the result doesn't depend on the shift quirk.
+
+        LD VC, 0
+
+        ; 8XY4 with a carry: #FF + 3 = 2 and VF = 1
+        LD V1, #FF
+        LD V2, 3
+        ADD V1, V2
+        LD V0, VF
+        CALL save
+        LD V0, V1
+        CALL save
+
+        ; 8XY4 without a carry: 5 + 6 = B and VF = 0
+        LD V1, 5
+        LD V2, 6
+        ADD V1, V2
+        LD V0, VF
+        CALL save
+        LD V0, V1
+        CALL save
+
+        ; 8XY5 without a borrow: 9 - 4 = 5 and VF = 1
+        LD V1, 9
+        LD V2, 4
+        SUB V1, V2
+        LD V0, VF
+        CALL save
+        LD V0, V1
+        CALL save
+
+        ; 8XY5 with a borrow: VF = 0
+        LD V1, 4
+        LD V2, 9
+        SUB V1, V2
+        LD V0, VF
+        CALL save
+
+        ; 8XY7 without a borrow: 9 - 4 = 5 and VF = 1
+        LD V1, 4
+        LD V2, 9
+        SUBN V1, V2
+        LD V0, VF
+        CALL save
+        LD V0, V1
+        CALL save
+
+        ; 8XY7 with a borrow: VF = 0
+        LD V1, 9
+        LD V2, 4
+        SUBN V1, V2
+        LD V0, VF
+        CALL save
+
+        ; 8XY6: 3 >> 1 = 1 and VF = 1
+        LD V1, 3
+        SHR V1, V1
+        LD V0, VF
+        CALL save
+        LD V0, V1
+        CALL save
+
+        ; 8XYE: #81 << 1 = 2 and VF = 1
+        LD V1, #81
+        SHL V1, V1
+        LD V0, V1
+        CALL save
+        LD V0, VF
+        CALL save
+
+        ; DXYN: VF = 0 on the first draw, 1 when the same sprite erases itself
+        LD V1, 40
+        LD V2, 26
+        LD V3, 8
+        LD F, V3
+        DRW V1, V2, 5
+        LD V0, VF
+        CALL save
+        LD F, V3
+        DRW V1, V2, 5
+        LD V0, VF
+        CALL save
+
+        JP report
+
+include "report.asm"
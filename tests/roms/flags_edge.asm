@@ -0,0 +1,44 @@
+; Corner cases of VF: subtractions of equal values don't borrow, and when VF is the destination
+; the flag is what remains in it.
+
+        LD VC, 0
+
+        ; 8XY5 with equal values: 5 - 5 = 0 and VF = 1
+        LD V1, 5
+        LD V2, 5
+        SUB V1, V2
+        LD V0, V1
+        CALL save
+        LD V0, VF
+        CALL save
+
+        ; 8XY7 with equal values: VF = 1
+        LD V1, 5
+        LD V2, 5
+        SUBN V1, V2
+        LD V0, VF
+        CALL save
+
+        ; 8FY4 with a carry: VF = 1
+        LD VF, #FF
+        LD V1, 3
+        ADD VF, V1
+        LD V0, VF
+        CALL save
+
+        ; 8FY5 without a borrow: VF = 1
+        LD VF, 9
+        LD V1, 4
+        SUB VF, V1
+        LD V0, VF
+        CALL save
+
+        ; 8FY6 shifting out a 0: VF = 0
+        LD VF, 2
+        SHR VF, VF
+        LD V0, VF
+        CALL save
+
+        JP report
+
+include "report.asm"
@@ -0,0 +1,37 @@
+; FX0A waits for key 5, then SKP and SKNP are checked with 5 pressed and 6 released.
+
+        LD VC, 0
+
+        LD V1, K
+        LD V0, V1
+        CALL save
+
+        LD V3, 6
+
+        ; SKP on a pressed key skips (0)
+        LD V0, 0
+        SKP V1
+        LD V0, 1
+        CALL save
+
+        ; SKNP on a pressed key doesn't skip (1)
+        LD V0, 0
+        SKNP V1
+        LD V0, 1
+        CALL save
+
+        ; SKP on a released key doesn't skip (1)
+        LD V0, 0
+        SKP V3
+        LD V0, 1
+        CALL save
+
+        ; SKNP on a released key skips (0)
+        LD V0, 0
+        SKNP V3
+        LD V0, 1
+        CALL save
+
+        JP report
+
+include "report.asm"
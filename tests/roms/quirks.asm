@@ -0,0 +1,80 @@
+; One result per quirk, each depends on whether the quirk is enabled.
+
+        LD VC, 0
+
+        ; vf_reset: 8XY1 sets VF to 0 (0), or leaves it alone (1)
+        LD VF, 1
+        LD V1, 0
+        LD V2, 0
+        OR V1, V2
+        LD V0, VF
+        CALL save
+
+        ; shift_uses_vy: 8XY6 shifts V2 = 8 (4), or V1 = 2 (1)
+        LD V1, 2
+        LD V2, 8
+        SHR V1, V2
+        LD V0, V1
+        CALL save
+
+        ; load_store_increments_i: FX65 after FX55 reads past the stored bytes (7), or reads them
+        ; again (3)
+        LD I, scratch
+        LD V0, 3
+        LD V1, 3
+        LD [I], V1
+        LD V0, [I]
+        CALL save
+
+        ; jump_uses_vx: BNNN adds V2 = 2 since NNN starts with 2 (1), or V0 = 0 (0)
+        LD V0, 0
+        LD V2, 2
+        JP V0, jump_target
+jump_target:
+        JP jump_without_vx
+        LD V0, 1
+        JP jump_done
+jump_without_vx:
+        LD V0, 0
+jump_done:
+        CALL save
+
+        ; clip_sprites: a sprite drawn at x = 60 is cut (0), or wraps and collides with one drawn
+        ; at x = 0 (1). Both are erased afterwards.
+        LD I, line
+        LD V1, 60
+        LD V2, 28
+        LD V3, 0
+        DRW V1, V2, 1
+        DRW V3, V2, 1
+        LD V0, VF
+        CALL save
+        LD I, line
+        DRW V3, V2, 1
+        DRW V1, V2, 1
+
+        ; display_wait: 5 draws take at least 4 frames (1), or happen within one frame (0)
+        LD V5, 10
+        LD DT, V5
+        LD I, blank
+        DRW V3, V3, 1
+        DRW V3, V3, 1
+        DRW V3, V3, 1
+        DRW V3, V3, 1
+        DRW V3, V3, 1
+        LD V7, DT
+        LD V8, 8
+        SUB V8, V7
+        LD V0, VF
+        CALL save
+
+        JP report
+
+line:
+        db #FF
+blank:
+        db #00
+scratch:
+        db 0, 0, 7
+
+include "report.asm"
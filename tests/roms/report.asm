@@ -0,0 +1,37 @@
+; Shared by the test ROMs. `save` appends V0 to the results and `report` draws every result as a
+; hex digit, 12 per row, then loops forever. VC holds the number of results, VB, VD and VE are
+; used by `report` only.
+
+save:
+        LD I, results
+        ADD I, VC
+        LD [I], V0
+        ADD VC, 1
+        RET
+
+report:
+        LD VB, 0
+        LD VD, 0
+        LD VE, 0
+report_loop:
+        SE VB, VC
+        JP report_draw
+report_done:
+        JP report_done
+report_draw:
+        LD I, results
+        ADD I, VB
+        LD V0, [I]
+        LD F, V0
+        DRW VD, VE, 5
+        ADD VB, 1
+        ADD VD, 5
+        SE VD, 60
+        JP report_loop
+        LD VD, 0
+        ADD VE, 6
+        JP report_loop
+
+results:
+        db 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
+        db 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
@@ -0,0 +1,60 @@
+// PNG screenshots are the framebuffer in the colours of the palette, at an integer scale.
+
+use chip_8_rs::{screenshot_png, Framebuffer, Palette};
+
+fn decode(png: &[u8]) -> (u32, u32, Vec<u8>) {
+    let decoder = png::Decoder::new(png);
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+
+    (info.width, info.height, pixels)
+}
+
+#[test]
+fn screenshots_are_scaled_in_the_palette_colours() {
+    let palette = Palette {
+        colors: [(1, 2, 3), (200, 100, 50), (0, 0, 0), (0, 0, 0)],
+    };
+
+    let mut framebuffer = Framebuffer::new();
+    framebuffer.set_pixel(1, 0, true);
+
+    let (width, height, pixels) = decode(&screenshot_png(&framebuffer, &palette, 3));
+
+    assert_eq!((width, height), (64 * 3, 32 * 3));
+
+    let color = |x: usize, y: usize| &pixels[(y * width as usize + x) * 3..][..3];
+
+    assert_eq!(color(2, 0), [1, 2, 3]);
+    assert_eq!(color(3, 0), [200, 100, 50]);
+    assert_eq!(color(5, 2), [200, 100, 50]);
+    assert_eq!(color(6, 2), [1, 2, 3]);
+    assert_eq!(color(4, 3), [1, 2, 3]);
+}
+
+#[test]
+fn palettes_are_read_as_hex_colours() {
+    let palette: Palette = "0F380F, #9bbc0f".parse().unwrap();
+    let default = Palette::default();
+
+    assert_eq!(
+        palette.colors,
+        [
+            (0x0F, 0x38, 0x0F),
+            (0x9B, 0xBC, 0x0F),
+            default.colors[2],
+            default.colors[3]
+        ]
+    );
+
+    for text in [
+        "",
+        "12345",
+        "GGGGGG",
+        "+12345",
+        "000000,000000,000000,000000,000000",
+    ] {
+        assert!(text.parse::<Palette>().is_err(), "{text}");
+    }
+}
@@ -0,0 +1,111 @@
+// Save states restore the whole machine, and anything that isn't a state of the same ROM and
+// variant is refused without touching the machine.
+
+use chip_8_rs::{screen_hash, Chip8, Chip8Error, Variant};
+
+fn load(path: &str, variant: Variant) -> Chip8 {
+    let mut chip8 = Chip8::with_variant(variant);
+    chip8.set_seed(0);
+    chip8.load_rom(&std::fs::read(path).unwrap()).unwrap();
+
+    chip8
+}
+
+fn run(chip8: &mut Chip8, frames: usize) {
+    for frame in 0..frames {
+        chip8.set_key((frame / 10 % 16) as u8, frame % 20 < 10);
+        chip8.run_frame().unwrap();
+    }
+}
+
+fn refusal(chip8: &mut Chip8, state: &[u8]) -> String {
+    let before = chip8.save_state();
+
+    let error = chip8.load_state(state).unwrap_err();
+    assert!(matches!(error, Chip8Error::InvalidSaveState(_)));
+    assert!(chip8.save_state() == before, "the machine changed");
+
+    error.to_string()
+}
+
+#[test]
+fn loading_a_state_restores_the_machine() {
+    for (path, variant) in [
+        ("programs/flightrunner.ch8", Variant::Chip8),
+        ("programs/danm8ku.ch8", Variant::XoChip),
+    ] {
+        let mut chip8 = load(path, variant);
+        run(&mut chip8, 90);
+
+        let state = chip8.save_state();
+        let screen = screen_hash(chip8.framebuffer());
+
+        run(&mut chip8, 60);
+
+        let mut other = load(path, variant);
+        other.load_state(&state).unwrap();
+        chip8.load_state(&state).unwrap();
+
+        assert!(other.save_state() == state, "{path}");
+        assert!(chip8.save_state() == state, "{path}");
+        assert_eq!(screen_hash(chip8.framebuffer()), screen, "{path}");
+
+        // Both go on the same way, random numbers included
+        run(&mut chip8, 60);
+        run(&mut other, 60);
+
+        assert!(other.save_state() == chip8.save_state(), "{path}");
+    }
+}
+
+#[test]
+fn states_of_another_rom_or_variant_are_refused() {
+    let state = load("programs/flightrunner.ch8", Variant::Chip8).save_state();
+
+    let mut other_rom = load("programs/heart_monitor.ch8", Variant::Chip8);
+    assert_eq!(
+        refusal(&mut other_rom, &state),
+        "The save state was made with a different ROM."
+    );
+
+    let mut other_variant = load("programs/flightrunner.ch8", Variant::SuperChip);
+    assert!(refusal(&mut other_variant, &state).contains("was made with the chip8 variant"));
+}
+
+#[test]
+fn bad_headers_are_refused() {
+    let mut chip8 = load("programs/flightrunner.ch8", Variant::Chip8);
+    let state = chip8.save_state();
+
+    let mut bad_magic = state.clone();
+    bad_magic[0] = b'X';
+    assert_eq!(refusal(&mut chip8, &bad_magic), "This is not a save state.");
+    assert_eq!(refusal(&mut chip8, b""), "This is not a save state.");
+
+    let mut bad_version = state.clone();
+    bad_version[4..6].copy_from_slice(&999u16.to_be_bytes());
+    assert!(
+        refusal(&mut chip8, &bad_version).starts_with("Save state version 999 is not supported")
+    );
+}
+
+#[test]
+fn truncated_or_padded_states_are_refused() {
+    let mut chip8 = load("programs/flightrunner.ch8", Variant::Chip8);
+    run(&mut chip8, 30);
+
+    let state = chip8.save_state();
+
+    for length in 0..state.len() {
+        refusal(&mut chip8, &state[..length]);
+    }
+
+    let mut padded = state.clone();
+    padded.push(0);
+    assert_eq!(
+        refusal(&mut chip8, &padded),
+        "The save state has unexpected trailing bytes."
+    );
+
+    chip8.load_state(&state).unwrap();
+}
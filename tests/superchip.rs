@@ -0,0 +1,188 @@
+// SUPER-CHIP instructions: scrolling, switching resolutions, 16x16 sprites, the big font, the
+// flag registers and exiting, checked on the screen and registers they leave.
+
+use chip_8_rs::{
+    assemble, Chip8, Quirks, QuirksPreset, Variant, BIG_FONT_DATA, HIRES_HEIGHT, HIRES_WIDTH, WIDTH,
+};
+
+// Draws a single pixel at (8, 8) in high resolution before running `code`
+const ONE_PIXEL: &str = "
+    high
+    ld i, pixel
+    ld v0, 8
+    ld v1, 8
+    drw v0, v1, 1
+";
+
+fn run(variant: Variant, source: &str) -> Chip8 {
+    let mut chip8 = Chip8::with_variant(variant);
+    chip8.set_quirks(Quirks::from_preset(QuirksPreset::SuperChip));
+    chip8.load_rom(&assemble(source).unwrap()).unwrap();
+
+    for _ in 0..2 {
+        chip8.run_frame().unwrap();
+    }
+
+    chip8
+}
+
+// `code` runs after ONE_PIXEL, the program exits afterwards
+fn run_after_one_pixel(code: &str) -> Chip8 {
+    run(
+        Variant::SuperChip,
+        &format!("{ONE_PIXEL}\n{code}\n    exit\npixel:\n    db 0x80\n"),
+    )
+}
+
+fn lit_pixels(chip8: &Chip8) -> Vec<(usize, usize)> {
+    let framebuffer = chip8.framebuffer();
+
+    (0..framebuffer.height())
+        .flat_map(|y| (0..framebuffer.width()).map(move |x| (x, y)))
+        .filter(|(x, y)| framebuffer.pixel(*x, *y))
+        .collect()
+}
+
+#[test]
+fn scrolling_moves_the_screen() {
+    for (code, expected) in [
+        ("", vec![(8, 8)]),
+        // 00CN scrolls down N rows, 00FB right and 00FC left 4 columns
+        ("    scd 3", vec![(8, 11)]),
+        ("    scr", vec![(12, 8)]),
+        ("    scl", vec![(4, 8)]),
+        ("    scl\n    scl\n    scl", vec![]),
+        ("    scd 15\n    scd 15\n    scd 15\n    scd 15", vec![]),
+    ] {
+        assert_eq!(lit_pixels(&run_after_one_pixel(code)), expected, "{code}");
+    }
+}
+
+#[test]
+fn low_resolution_scrolls_by_low_resolution_pixels() {
+    let chip8 = run_after_one_pixel("    low\n    drw v0, v1, 1\n    scd 2\n    scr");
+
+    assert!(!chip8.framebuffer().is_hires());
+    assert_eq!(lit_pixels(&chip8), [(12, 10)]);
+}
+
+#[test]
+fn switching_resolution_clears_the_screen() {
+    let chip8 = run_after_one_pixel("");
+    assert!(chip8.framebuffer().is_hires());
+    assert_eq!(
+        (chip8.framebuffer().width(), chip8.framebuffer().height()),
+        (HIRES_WIDTH, HIRES_HEIGHT)
+    );
+
+    for code in ["    low", "    high"] {
+        let chip8 = run_after_one_pixel(code);
+
+        assert_eq!(chip8.framebuffer().is_hires(), code.contains("high"));
+        assert_eq!(lit_pixels(&chip8), [], "{code}");
+    }
+
+    // High resolution pixels past the low resolution screen don't wrap
+    let chip8 = run_after_one_pixel("    ld v0, 100\n    ld v1, 50\n    drw v0, v1, 1");
+    assert_eq!(lit_pixels(&chip8), [(8, 8), (100, 50)]);
+}
+
+#[test]
+fn dxy0_draws_16x16_sprites() {
+    let source = "
+    high
+    ld i, sprite
+    ld v0, 10
+    ld v1, 5
+    drw v0, v1, 0
+    ld v2, vf
+    drw v0, v1, 0
+    exit
+end:
+    jp end
+sprite:
+    dw 0x8001, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xC003
+";
+
+    let chip8 = run(
+        Variant::SuperChip,
+        &source.replace("    ld v2, vf\n    drw v0, v1, 0\n", ""),
+    );
+    assert_eq!(
+        lit_pixels(&chip8),
+        [(10, 5), (25, 5), (10, 20), (11, 20), (24, 20), (25, 20)]
+    );
+
+    // Drawing it again erases it and reports the collision
+    let chip8 = run(Variant::SuperChip, source);
+    assert_eq!(lit_pixels(&chip8), []);
+    assert_eq!(chip8.registers()[0x2], 0);
+    assert_eq!(chip8.registers()[0xF], 1);
+
+    // DXY0 draws nothing on the original CHIP-8
+    let chip8 = run(Variant::Chip8, &source.replace("    high\n", ""));
+    assert_eq!(lit_pixels(&chip8), []);
+}
+
+#[test]
+fn big_font_digits_are_10_bytes_each() {
+    for digit in [0, 7, 9] {
+        let chip8 = run(
+            Variant::SuperChip,
+            &format!("    ld v3, {digit}\n    ld hf, v3\n    exit\n"),
+        );
+
+        let glyph = chip8.read_ram(chip8.i() as usize, 10).unwrap();
+        assert_eq!(glyph, &BIG_FONT_DATA[digit * 10..][..10], "digit {digit}");
+    }
+}
+
+#[test]
+fn flag_registers_keep_the_registers_saved() {
+    let chip8 = run(
+        Variant::SuperChip,
+        "
+    ld v0, 1
+    ld v1, 2
+    ld v2, 3
+    ld v3, 4
+    ld r, v3
+    ld v0, 0
+    ld v1, 0
+    ld v2, 0
+    ld v3, 0
+    ld v2, r
+    exit
+",
+    );
+
+    // Only V0 to VX are loaded back
+    assert_eq!(chip8.registers()[..4], [1, 2, 3, 0]);
+}
+
+#[test]
+fn exit_halts_the_machine() {
+    let source = "
+    ld v0, 1
+    exit
+    ld v0, 2
+loop:
+    jp loop
+";
+
+    let mut chip8 = run(Variant::SuperChip, source);
+    let instructions = chip8.instruction_count();
+
+    assert!(chip8.is_halted());
+    assert_eq!(chip8.registers()[0], 1);
+
+    chip8.run_frame().unwrap();
+    assert_eq!(chip8.instruction_count(), instructions);
+
+    // 00FD is a machine code call the original CHIP-8 skips
+    let chip8 = run(Variant::Chip8, source);
+
+    assert!(!chip8.is_halted());
+    assert_eq!(chip8.registers()[0], 2);
+    assert_eq!(chip8.framebuffer().width(), WIDTH);
+}
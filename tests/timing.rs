@@ -0,0 +1,144 @@
+// Instructions run per frame with the clock rate and with the COSMAC VIP timing.
+
+use chip_8_rs::{
+    assemble, Chip8, Quirks, QuirksPreset, Timing, FRAME_RATE, MAX_CLOCK_RATE,
+    VIP_INTERPRETER_CYCLES,
+};
+
+fn load(source: &str) -> Chip8 {
+    let mut chip8 = Chip8::new();
+    chip8.load_rom(&assemble(source).unwrap()).unwrap();
+
+    chip8
+}
+
+fn instructions_per_frame(chip8: &mut Chip8, frames: usize) -> Vec<u64> {
+    (0..frames)
+        .map(|_| {
+            let before = chip8.instruction_count();
+            chip8.run_frame().unwrap();
+
+            chip8.instruction_count() - before
+        })
+        .collect()
+}
+
+#[test]
+fn clock_rate_is_exact_over_a_second() {
+    let mut chip8 = load("loop:\n    jp loop\n");
+    chip8.set_clock_rate(1000);
+
+    let counts = instructions_per_frame(&mut chip8, 60);
+
+    assert_eq!(counts.iter().sum::<u64>(), 1000);
+    assert!(counts.iter().all(|count| *count == 16 || *count == 17));
+}
+
+#[test]
+fn clock_rates_are_capped() {
+    // The frame ends on the draw, the display waits for the interrupt
+    let mut chip8 = load("loop:\n    drw v0, v0, 1\n    jp loop\n");
+    chip8.set_quirks(Quirks::from_preset(QuirksPreset::CosmacVip));
+
+    chip8.set_clock_rate(u32::MAX);
+    assert_eq!(chip8.clock_rate(), MAX_CLOCK_RATE);
+
+    for _ in 0..FRAME_RATE {
+        chip8.run_frame().unwrap();
+    }
+
+    let error = chip8.set_instructions_per_frame(100_000_000).unwrap_err();
+    assert!(error.contains("over the maximum clock rate"), "{error}");
+    assert!(chip8.set_instructions_per_frame(usize::MAX).is_err());
+    assert_eq!(chip8.clock_rate(), MAX_CLOCK_RATE);
+
+    chip8.set_instructions_per_frame(10).unwrap();
+    assert_eq!(chip8.clock_rate(), 10 * FRAME_RATE);
+}
+
+#[test]
+fn vip_timing_spends_the_cycles_of_a_frame() {
+    // LD costs 6 cycles and JP 12, out of 2604 per frame. The first frame overruns by 6 cycles,
+    // which the second one pays back
+    let mut chip8 = load("loop:\n    ld v0, 1\n    jp loop\n");
+    chip8.set_timing(Timing::CosmacVip);
+
+    assert_eq!(instructions_per_frame(&mut chip8, 2), [290, 289]);
+}
+
+// The instructions per frame of a loop whose instructions cost `cycles` in turn, overruns being
+// paid back in the next frame like the interpreter does
+fn vip_instructions_per_frame(cycles: &[i64], frames: usize) -> Vec<u64> {
+    let mut budget = 0;
+    let mut costs = cycles.iter().cycle();
+
+    (0..frames)
+        .map(|_| {
+            let mut count = 0;
+            budget = budget.min(0) + VIP_INTERPRETER_CYCLES;
+
+            while budget > 0 {
+                budget -= costs.next().unwrap();
+                count += 1;
+            }
+
+            count
+        })
+        .collect()
+}
+
+#[test]
+fn vip_timing_charges_the_interpreter_cycles() {
+    let programs: [(&str, &[i64]); 8] = [
+        // 8XYN: 44, JP: 12
+        ("loop:\n    ld v1, v2\n    jp loop\n", &[44, 12]),
+        // ADD: 10
+        ("loop:\n    add v0, 1\n    jp loop\n", &[10, 12]),
+        // A taken SE: 10 + 4
+        (
+            "loop:\n    se v0, 0\n    ld v0, v0\n    jp loop\n",
+            &[14, 12],
+        ),
+        // An untaken SNE: 14, then 8XY0: 44
+        (
+            "loop:\n    sne v0, v1\n    ld v0, v1\n    jp loop\n",
+            &[14, 44, 12],
+        ),
+        // CALL: 26, RET: 10
+        (
+            "loop:\n    call sub\n    jp loop\nsub:\n    ret\n",
+            &[26, 10, 12],
+        ),
+        // RND: 36, FX33: 204
+        (
+            "loop:\n    rnd v0, 255\n    ld b, v0\n    jp loop\n",
+            &[36, 204, 12],
+        ),
+        // FX55 and FX65: 14 per register plus 14
+        (
+            "loop:\n    ld [i], v3\n    ld v3, [i]\n    jp loop\n",
+            &[70, 70, 12],
+        ),
+        // Instructions the VIP doesn't have, a machine code call here: 10
+        ("loop:\n    sys 0x123\n    jp loop\n", &[10, 12]),
+    ];
+
+    for (source, cycles) in programs {
+        let mut chip8 = load(source);
+        chip8.set_timing(Timing::CosmacVip);
+
+        assert_eq!(
+            instructions_per_frame(&mut chip8, 3),
+            vip_instructions_per_frame(cycles, 3),
+            "{source}"
+        );
+    }
+}
+
+#[test]
+fn vip_timing_draws_one_sprite_per_frame() {
+    let mut chip8 = load("loop:\n    drw v0, v0, 5\n    jp loop\n");
+    chip8.set_timing(Timing::CosmacVip);
+
+    assert_eq!(instructions_per_frame(&mut chip8, 3), [1, 2, 2]);
+}
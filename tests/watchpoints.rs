@@ -0,0 +1,115 @@
+// Watchpoints on memory reads and writes, register values and the range of I, hit by the
+// program's own accesses only, with the interpreter and with translated blocks.
+
+use chip_8_rs::{assemble, Backend, Chip8, WatchHit, Watchpoint};
+
+const PROGRAM: &str = "
+    ld i, data
+    ld v0, [i]
+    ld v1, 5
+    ld i, 0x300
+    ld [i], v1
+loop:
+    jp loop
+data:
+    db 7
+";
+
+const DATA: u16 = 0x20C;
+
+// Runs a frame up to the first hit
+fn first_hit(watchpoints: &[Watchpoint], backend: Backend) -> Option<WatchHit> {
+    let mut chip8 = Chip8::new();
+    chip8.set_backend(backend);
+    chip8.load_rom(&assemble(PROGRAM).unwrap()).unwrap();
+
+    for watchpoint in watchpoints {
+        chip8.add_watchpoint(*watchpoint);
+    }
+
+    chip8
+        .run_frame_until(|chip8| chip8.watch_hit().is_some())
+        .unwrap()
+        .then(|| chip8.watch_hit().unwrap())
+}
+
+#[test]
+fn watchpoints_are_hit_by_the_instruction_making_the_access() {
+    for backend in Backend::ALL {
+        for (watchpoint, address) in [
+            (Watchpoint::MemoryRead(DATA), 0x202),
+            (Watchpoint::Register { index: 1, value: 5 }, 0x204),
+            (
+                Watchpoint::IndexInRange {
+                    start: 0x2F0,
+                    end: 0x3FF,
+                },
+                0x206,
+            ),
+            (Watchpoint::MemoryWrite(0x301), 0x208),
+        ] {
+            assert_eq!(
+                first_hit(&[watchpoint], backend),
+                Some(WatchHit {
+                    watchpoint,
+                    address
+                }),
+                "{watchpoint} with {backend}"
+            );
+        }
+    }
+}
+
+#[test]
+fn watchpoints_are_not_hit_by_other_accesses() {
+    for backend in Backend::ALL {
+        let misses = [
+            // Fetching instructions isn't a read
+            Watchpoint::MemoryRead(0x200),
+            Watchpoint::MemoryRead(DATA + 1),
+            Watchpoint::MemoryWrite(0x302),
+            Watchpoint::Register { index: 1, value: 6 },
+            Watchpoint::IndexInRange {
+                start: 0x400,
+                end: 0xFFF,
+            },
+        ];
+
+        assert_eq!(first_hit(&misses, backend), None, "{backend}");
+    }
+}
+
+#[test]
+fn removed_watchpoints_are_not_hit() {
+    let mut chip8 = Chip8::new();
+    chip8.load_rom(&assemble(PROGRAM).unwrap()).unwrap();
+
+    let read = Watchpoint::MemoryRead(DATA);
+    let register = Watchpoint::Register { index: 1, value: 5 };
+    chip8.add_watchpoint(read);
+    chip8.add_watchpoint(register);
+    chip8.add_watchpoint(register);
+
+    assert_eq!(chip8.watchpoints(), [read, register]);
+    assert!(chip8.remove_watchpoint(&read));
+    assert!(chip8.remove_watchpoint(&register));
+    assert!(!chip8.remove_watchpoint(&register));
+
+    assert!(!chip8
+        .run_frame_until(|chip8| chip8.watch_hit().is_some())
+        .unwrap());
+}
+
+#[test]
+fn tools_reading_and_writing_memory_do_not_hit_watchpoints() {
+    let mut chip8 = Chip8::new();
+    chip8.add_watchpoint(Watchpoint::MemoryRead(0x300));
+    chip8.add_watchpoint(Watchpoint::MemoryWrite(0x300));
+
+    chip8.write_ram(0x300, &[1]).unwrap();
+    chip8.read_ram(0x300, 1).unwrap();
+
+    // 0000 at 0x200 doesn't access memory, a hit from the tools would show after it
+    chip8.step().unwrap();
+    assert_eq!(chip8.watch_hit(), None);
+}
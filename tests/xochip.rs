@@ -0,0 +1,165 @@
+// XO-CHIP instructions: saving and loading register ranges, drawing, clearing and scrolling the
+// selected planes, scrolling up, the 4 byte F000 NNNN and the audio pattern and pitch.
+
+use chip_8_rs::{assemble, Chip8, Variant};
+
+fn run(source: &str) -> Chip8 {
+    let mut chip8 = Chip8::with_variant(Variant::XoChip);
+    chip8
+        .load_rom(&assemble(&format!("{source}\nend:\n    jp end\n")).unwrap())
+        .unwrap();
+
+    for _ in 0..2 {
+        chip8.run_frame().unwrap();
+    }
+
+    chip8
+}
+
+// Pixels that aren't off, with their colour: bit 0 is the first plane and bit 1 the second
+fn colored_pixels(chip8: &Chip8) -> Vec<(usize, usize, u8)> {
+    let framebuffer = chip8.framebuffer();
+
+    (0..framebuffer.height())
+        .flat_map(|y| (0..framebuffer.width()).map(move |x| (x, y)))
+        .map(|(x, y)| (x, y, framebuffer.color(x, y)))
+        .filter(|(_, _, color)| *color != 0)
+        .collect()
+}
+
+#[test]
+fn register_ranges_are_saved_and_loaded_in_either_order() {
+    let chip8 = run("
+    ld v1, 1
+    ld v2, 2
+    ld v3, 3
+    ld i, buffer
+    save v1, v3
+    ld i, reversed
+    save v3, v1
+    ld i, buffer
+    load v5, v7
+    load vc, va
+    jp end
+buffer:
+    db 0, 0, 0
+reversed:
+    db 0, 0, 0
+");
+
+    let buffer = chip8.i() as usize;
+    assert_eq!(chip8.read_ram(buffer, 6).unwrap(), [1, 2, 3, 3, 2, 1]);
+
+    // I is left alone, X greater than Y goes backwards
+    assert_eq!(chip8.registers()[0x5..=0x7], [1, 2, 3]);
+    assert_eq!(chip8.registers()[0xA..=0xC], [3, 2, 1]);
+}
+
+#[test]
+fn sprites_are_drawn_to_the_selected_planes() {
+    let chip8 = run("
+    ld v0, 8
+    ld v1, 8
+    ld i, sprites
+    plane 2
+    drw v0, v1, 1
+    ld v0, 16
+    plane 3
+    drw v0, v1, 1
+    jp end
+sprites:
+    db 0x80, 0xC0
+");
+
+    // The first plane gets the sprite at I and the second plane the one after it
+    assert_eq!(
+        colored_pixels(&chip8),
+        [(8, 8, 0b10), (16, 8, 0b11), (17, 8, 0b10)]
+    );
+}
+
+#[test]
+fn clearing_and_scrolling_only_change_the_selected_planes() {
+    let setup = "
+    ld v0, 8
+    ld v1, 8
+    ld i, sprites
+    plane 3
+    drw v0, v1, 1
+";
+    let sprites = "
+    jp end
+sprites:
+    db 0x80, 0xC0
+";
+
+    for (code, expected) in [
+        ("plane 1\n    cls", vec![(8, 8, 0b10), (9, 8, 0b10)]),
+        ("plane 2\n    cls", vec![(8, 8, 0b01)]),
+        ("plane 0\n    cls", vec![(8, 8, 0b11), (9, 8, 0b10)]),
+        ("plane 3\n    cls", vec![]),
+        // 00DN scrolls up N rows
+        (
+            "plane 2\n    scu 3",
+            vec![(8, 5, 0b10), (9, 5, 0b10), (8, 8, 0b01)],
+        ),
+        (
+            "plane 1\n    scd 2",
+            vec![(8, 8, 0b10), (9, 8, 0b10), (8, 10, 0b01)],
+        ),
+        ("plane 3\n    scr", vec![(12, 8, 0b11), (13, 8, 0b10)]),
+        (
+            "plane 1\n    scl",
+            vec![(4, 8, 0b01), (8, 8, 0b10), (9, 8, 0b10)],
+        ),
+    ] {
+        let chip8 = run(&format!("{setup}    {code}{sprites}"));
+
+        assert_eq!(colored_pixels(&chip8), expected, "{code}");
+    }
+}
+
+#[test]
+fn f000_loads_a_16_bit_address_and_is_skipped_whole() {
+    let chip8 = run("    ld i, long 0xABCD");
+    assert_eq!(chip8.i(), 0xABCD);
+
+    // A skip jumps over the 4 bytes, it would run 0x1234 as a jump otherwise
+    for (skip, i) in [("se v0, 0", 0), ("sne v0, 0", 0x1234)] {
+        let chip8 = run(&format!("    {skip}\n    ld i, long 0x1234\n    ld v1, 1"));
+
+        assert_eq!(chip8.i(), i, "{skip}");
+        assert_eq!(chip8.registers()[1], 1, "{skip}");
+    }
+}
+
+#[test]
+fn audio_pattern_and_pitch_are_loaded() {
+    let chip8 = run("    ld v0, 100");
+    assert_eq!(chip8.audio_pattern(), None);
+
+    let chip8 = run("
+    ld i, pattern
+    audio
+    ld v0, 100
+    pitch v0
+    jp end
+pattern:
+    db 0xFF, 0, 0xFF, 0, 0xFF, 0, 0xFF, 0, 0xF0, 0x0F, 0xF0, 0x0F, 1, 2, 3, 4
+");
+
+    let pattern = [
+        0xFF, 0, 0xFF, 0, 0xFF, 0, 0xFF, 0, 0xF0, 0x0F, 0xF0, 0x0F, 1, 2, 3, 4,
+    ];
+    assert_eq!(chip8.audio_pattern(), Some((&pattern, 100)));
+
+    // The pitch starts at 64, 4000Hz
+    let chip8 = run("
+    ld i, pattern
+    audio
+    jp end
+pattern:
+    db 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
+");
+    assert_eq!(chip8.audio_pattern(), Some((&[0; 16], 64)));
+}